@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 
 use failure::Fail;
 
-use geometry::{Direction, Point};
+use geometry::{Direction, Mirror, Point};
 
 use crate::layout::Track;
 
@@ -46,16 +46,10 @@ impl Turn {
     }
 
     fn advance(self, direction: Direction) -> (Self, Direction) {
-        let direction = match (self, direction) {
-            (Turn::Left, Direction::Left) => Direction::Down,
-            (Turn::Left, Direction::Down) => Direction::Right,
-            (Turn::Left, Direction::Right) => Direction::Up,
-            (Turn::Left, Direction::Up) => Direction::Left,
-            (Turn::Straight, _) => direction,
-            (Turn::Right, Direction::Left) => Direction::Up,
-            (Turn::Right, Direction::Down) => Direction::Left,
-            (Turn::Right, Direction::Right) => Direction::Down,
-            (Turn::Right, Direction::Up) => Direction::Right,
+        let direction = match self {
+            Turn::Left => direction.turn_left(),
+            Turn::Straight => direction,
+            Turn::Right => direction.turn_right(),
         };
         (self.next(), direction)
     }
@@ -102,14 +96,8 @@ impl Cart {
             (Direction::Up, Track::Vertical) => Ok(Direction::Up),
             (Direction::Down, Track::Vertical) => Ok(Direction::Down),
             (_, Track::Vertical) => Err(CartAdvanceError::OffTheRails),
-            (Direction::Up, Track::LeftCorner) => Ok(Direction::Left),
-            (Direction::Right, Track::LeftCorner) => Ok(Direction::Down),
-            (Direction::Down, Track::LeftCorner) => Ok(Direction::Right),
-            (Direction::Left, Track::LeftCorner) => Ok(Direction::Up),
-            (Direction::Up, Track::RightCorner) => Ok(Direction::Right),
-            (Direction::Right, Track::RightCorner) => Ok(Direction::Up),
-            (Direction::Down, Track::RightCorner) => Ok(Direction::Left),
-            (Direction::Left, Track::RightCorner) => Ok(Direction::Down),
+            (d, Track::LeftCorner) => Ok(d.reflect(Mirror::Backslash)),
+            (d, Track::RightCorner) => Ok(d.reflect(Mirror::Slash)),
             (d, Track::Intersection) => {
                 let (turn, d) = self.intersection.advance(d);
                 self.intersection = turn;
@@ -0,0 +1,246 @@
+//! Parse ASCII maps into grids, and render them back into text.
+
+use std::convert::TryFrom;
+
+use failure::Fail;
+
+use crate::grid::Grid;
+use crate::{BoundingBox, Point, Position};
+
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum ParseMapError<E>
+where
+    E: Fail,
+{
+    #[fail(
+        display = "Line {}, column {}: Invalid cell {:?}: {}",
+        line, column, character, cause
+    )]
+    Cell {
+        line: usize,
+        column: usize,
+        character: char,
+        #[cause]
+        cause: E,
+    },
+
+    #[fail(
+        display = "Map is {}x{}, larger than the limit of {}x{}",
+        _0, _1, _2, _3
+    )]
+    TooLarge(usize, usize, usize, usize),
+}
+
+/// Reads maps where each character is one cell, like the puzzle inputs
+/// for carts, caves, ground and lumber.
+///
+/// Lines shorter than the longest line are padded as if they ended
+/// with spaces, and trailing blank lines are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapParser {
+    origin: Point,
+    limit: Option<(usize, usize)>,
+}
+
+impl Default for MapParser {
+    fn default() -> Self {
+        Self {
+            origin: Point::new(0, 0),
+            limit: None,
+        }
+    }
+}
+
+impl MapParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Place the first character of the first line at `origin`.
+    pub fn with_origin(mut self, origin: Point) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Reject maps wider or taller than these limits.
+    pub fn with_limit(mut self, width: usize, height: usize) -> Self {
+        self.limit = Some((width, height));
+        self
+    }
+
+    pub fn parse<T>(&self, s: &str) -> Result<Grid<T>, ParseMapError<T::Error>>
+    where
+        T: TryFrom<char>,
+        T::Error: Fail,
+    {
+        self.parse_with(s, |_, _| None)
+    }
+
+    /// Parse a map which may contain entities drawn over the cells, like
+    /// carts on tracks or sprites in a cave. The overlay is given each
+    /// point and character first, and returns the cell underneath if it
+    /// recognized an entity there.
+    pub fn parse_with<T, F>(
+        &self,
+        s: &str,
+        mut overlay: F,
+    ) -> Result<Grid<T>, ParseMapError<T::Error>>
+    where
+        T: TryFrom<char>,
+        T::Error: Fail,
+        F: FnMut(Point, char) -> Option<T>,
+    {
+        let mut lines: Vec<&str> = s.lines().collect();
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let height = lines.len();
+        if let Some((max_width, max_height)) = self.limit {
+            if width > max_width || height > max_height {
+                return Err(ParseMapError::TooLarge(
+                    width, height, max_width, max_height,
+                ));
+            }
+        }
+
+        let bbox = BoundingBox::new(
+            self.origin.x,
+            self.origin.x + width as Position - 1,
+            self.origin.y,
+            self.origin.y + height as Position - 1,
+        );
+
+        let mut cells = Vec::with_capacity(width * height);
+        for (y, line) in lines.iter().enumerate() {
+            let padding = width - line.chars().count();
+            let characters = line.chars().chain((0..padding).map(|_| ' '));
+            for (x, c) in characters.enumerate() {
+                let point =
+                    Point::new(self.origin.x + x as Position, self.origin.y + y as Position);
+                let cell = match overlay(point, c) {
+                    Some(cell) => cell,
+                    None => T::try_from(c).map_err(|cause| ParseMapError::Cell {
+                        line: y + 1,
+                        column: x + 1,
+                        character: c,
+                        cause,
+                    })?,
+                };
+                cells.push(cell);
+            }
+        }
+
+        Ok(Grid::from_cells(bbox, cells))
+    }
+}
+
+/// Render a grid as text, one character per cell, with a line per row.
+/// The function can draw entities over a cell, the inverse of the
+/// overlay in `MapParser::parse_with`.
+pub fn render<T, F>(grid: &Grid<T>, mut cell: F) -> String
+where
+    F: FnMut(Point, &T) -> char,
+{
+    let mut text = String::with_capacity(grid.len() + grid.height());
+    let bbox = grid.bbox();
+    for (point, value) in grid.iter() {
+        text.push(cell(point, value));
+        if point.x == bbox.right() {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Tile {
+        Wall,
+        Open,
+    }
+
+    #[derive(Debug, Fail, PartialEq, Eq)]
+    #[fail(display = "Unknown tile")]
+    struct UnknownTile;
+
+    impl TryFrom<char> for Tile {
+        type Error = UnknownTile;
+
+        fn try_from(c: char) -> Result<Self, Self::Error> {
+            match c {
+                '#' => Ok(Tile::Wall),
+                '.' | ' ' => Ok(Tile::Open),
+                _ => Err(UnknownTile),
+            }
+        }
+    }
+
+    fn draw(tile: Tile) -> char {
+        match tile {
+            Tile::Wall => '#',
+            Tile::Open => '.',
+        }
+    }
+
+    #[test]
+    fn parse_and_render() {
+        let grid: Grid<Tile> = MapParser::new().parse("###\n#.\n###\n\n").unwrap();
+        assert_eq!(grid.bbox(), BoundingBox::new(0, 2, 0, 2));
+        assert_eq!(grid[Point::new(2, 1)], Tile::Open);
+        assert_eq!(render(&grid, |_, t| draw(*t)), "###\n#..\n###\n");
+
+        let grid: Grid<Tile> = MapParser::new()
+            .with_origin(Point::new(-1, 5))
+            .parse("#.")
+            .unwrap();
+        assert_eq!(grid[Point::new(0, 5)], Tile::Open);
+    }
+
+    #[test]
+    fn overlay() {
+        let mut sprites = Vec::new();
+        let grid: Grid<Tile> = MapParser::new()
+            .parse_with("#E#\n#G#", |point, c| match c {
+                'E' | 'G' => {
+                    sprites.push((point, c));
+                    Some(Tile::Open)
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            sprites,
+            vec![(Point::new(1, 0), 'E'), (Point::new(1, 1), 'G')]
+        );
+        assert_eq!(
+            render(&grid, |p, t| sprites
+                .iter()
+                .find(|(s, _)| *s == p)
+                .map_or(draw(*t), |(_, c)| *c)),
+            "#E#\n#G#\n"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            MapParser::new().parse::<Tile>("##\n#x"),
+            Err(ParseMapError::Cell {
+                line: 2,
+                column: 2,
+                character: 'x',
+                cause: UnknownTile
+            })
+        );
+        assert_eq!(
+            MapParser::new().with_limit(2, 2).parse::<Tile>("###\n#.#"),
+            Err(ParseMapError::TooLarge(3, 2, 2, 2))
+        );
+    }
+}
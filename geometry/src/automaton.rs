@@ -0,0 +1,462 @@
+//! Cellular automata on a fixed grid, or on an unbounded line.
+//!
+//! Both kinds can jump ahead to a distant generation, by remembering
+//! each generation until one repeats an earlier one. On a line, a
+//! pattern which repeats in a different place counts as a repeat,
+//! so patterns which drift along forever can be extrapolated too.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use failure::Fail;
+
+use crate::grid::Grid;
+use crate::{Compass, Direction, Point};
+
+/// Earlier states of an automaton, indexed by their hash.
+struct History<K> {
+    states: Vec<K>,
+    index: HashMap<u64, Vec<usize>>,
+}
+
+impl<K: Hash + Eq> History<K> {
+    fn new() -> Self {
+        Self {
+            states: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Remember a state, returning the position of an identical state
+    /// which was recorded earlier.
+    fn record(&mut self, state: K) -> Option<usize> {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let states = &self.states;
+        let earlier = self
+            .index
+            .get(&hash)
+            .and_then(|candidates| candidates.iter().find(|&&i| states[i] == state))
+            .cloned();
+
+        if earlier.is_none() {
+            self.index.entry(hash).or_default().push(self.states.len());
+        }
+        self.states.push(state);
+        earlier
+    }
+
+    fn get(&self, position: usize) -> &K {
+        &self.states[position]
+    }
+}
+
+/// Where a repeat was found, and which recorded state matches the target.
+struct Jump {
+    earlier: usize,
+    cycles: u64,
+    state: usize,
+}
+
+impl Jump {
+    /// Having just recorded `generation`, which repeats the state
+    /// recorded at position `earlier` of a history which started at
+    /// `start`, work out which state will be seen at `target`.
+    fn new(start: u64, earlier: usize, generation: u64, target: u64) -> Self {
+        let length = generation - (start + earlier as u64);
+        let remaining = target - generation;
+        Self {
+            earlier,
+            cycles: remaining / length + 1,
+            state: earlier + (remaining % length) as usize,
+        }
+    }
+}
+
+/// The cells around each cell which are given to the rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The four adjacent cells, in reading order.
+    VonNeumann,
+
+    /// The eight surrounding cells, in reading order.
+    Moore,
+
+    /// Cells at arbitrary offsets, in the order given.
+    Offsets(Vec<Point>),
+}
+
+impl Neighbourhood {
+    fn offsets(&self) -> Vec<Point> {
+        let origin = Point::new(0, 0);
+        match self {
+            Neighbourhood::VonNeumann => Direction::all().map(|d| origin.step(d)).collect(),
+            Neighbourhood::Moore => Compass::all().map(|c| origin.step_compass(c)).collect(),
+            Neighbourhood::Offsets(offsets) => offsets.clone(),
+        }
+    }
+}
+
+/// A cellular automaton on a grid of fixed size. Cells beyond the edge
+/// of the grid always have the boundary value.
+///
+/// The rule is given each cell and its neighbours, and returns the
+/// cell's value in the next generation.
+pub struct Automaton<T, R> {
+    current: Grid<T>,
+    next: Grid<T>,
+    offsets: Vec<Point>,
+    boundary: T,
+    rule: R,
+    generation: u64,
+}
+
+impl<T, R> Automaton<T, R>
+where
+    T: Clone + Hash + Eq,
+    R: FnMut(&T, &[T]) -> T,
+{
+    /// An automaton starting from `grid`, using the Moore neighbourhood.
+    pub fn new(grid: Grid<T>, boundary: T, rule: R) -> Self {
+        Self {
+            next: grid.clone(),
+            current: grid,
+            offsets: Neighbourhood::Moore.offsets(),
+            boundary,
+            rule,
+            generation: 0,
+        }
+    }
+
+    pub fn with_neighbourhood(mut self, neighbourhood: Neighbourhood) -> Self {
+        self.offsets = neighbourhood.offsets();
+        self
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn grid(&self) -> &Grid<T> {
+        &self.current
+    }
+
+    pub fn into_grid(self) -> Grid<T> {
+        self.current
+    }
+
+    pub fn step(&mut self) {
+        let mut neighbours = Vec::with_capacity(self.offsets.len());
+        for (point, cell) in self.next.iter_mut() {
+            neighbours.clear();
+            for offset in &self.offsets {
+                let neighbour = Point::new(point.x + offset.x, point.y + offset.y);
+                neighbours.push(
+                    self.current
+                        .get(neighbour)
+                        .unwrap_or(&self.boundary)
+                        .clone(),
+                );
+            }
+            *cell = (self.rule)(&self.current[point], &neighbours);
+        }
+        mem::swap(&mut self.current, &mut self.next);
+        self.generation += 1;
+    }
+
+    /// Step through generations until reaching `target`, skipping
+    /// ahead once the grid repeats an earlier generation.
+    pub fn advance_to(&mut self, target: u64) {
+        assert!(
+            target >= self.generation,
+            "Can't go back to generation {} from {}",
+            target,
+            self.generation
+        );
+
+        let start = self.generation;
+        let mut history = History::new();
+        history.record(self.current.values().cloned().collect::<Vec<_>>());
+
+        while self.generation < target {
+            self.step();
+
+            let cells = self.current.values().cloned().collect::<Vec<_>>();
+            if let Some(earlier) = history.record(cells) {
+                let jump = Jump::new(start, earlier, self.generation, target);
+                let cells = history.get(jump.state).clone();
+                self.current = Grid::from_cells(self.current.bbox(), cells);
+                self.generation = target;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum AutomatonError {
+    #[fail(display = "The rule changes cells surrounded by background")]
+    BackgroundChanges,
+}
+
+/// A cellular automaton on an unbounded line of cells. Cells are only
+/// stored between the first and last cells which differ from the
+/// background, and the line grows as the pattern spreads.
+///
+/// The rule is given a window of cells centred on each cell, which
+/// reaches `radius` cells to each side.
+///
+/// The rule must map a window of nothing but background to background,
+/// otherwise the whole infinite line would change at once.
+pub struct LineAutomaton<T, R> {
+    cells: Vec<T>,
+    next: Vec<T>,
+    offset: i64,
+    background: T,
+    radius: usize,
+    rule: R,
+    generation: u64,
+}
+
+impl<T, R> LineAutomaton<T, R>
+where
+    T: Clone + Hash + Eq,
+    R: FnMut(&[T]) -> T,
+{
+    /// An automaton whose first cell is at position zero, looking at
+    /// `radius` cells to either side. Fails if the rule changes a
+    /// window of nothing but background.
+    pub fn new(
+        cells: Vec<T>,
+        background: T,
+        radius: usize,
+        mut rule: R,
+    ) -> Result<Self, AutomatonError> {
+        if rule(&vec![background.clone(); 2 * radius + 1]) != background {
+            return Err(AutomatonError::BackgroundChanges);
+        }
+
+        let mut automaton = Self {
+            cells,
+            next: Vec::new(),
+            offset: 0,
+            background,
+            radius,
+            rule,
+            generation: 0,
+        };
+        automaton.trim();
+        Ok(automaton)
+    }
+
+    /// Place the first of the cells given to `new` at `offset`.
+    pub fn with_offset(mut self, offset: i64) -> Self {
+        self.offset += offset;
+        self
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The position of the first stored cell.
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn get(&self, position: i64) -> &T {
+        let index = position - self.offset;
+        if index < 0 {
+            return &self.background;
+        }
+        self.cells.get(index as usize).unwrap_or(&self.background)
+    }
+
+    /// Each stored cell and its position.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, &T)> {
+        let offset = self.offset;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (offset + i as i64, cell))
+    }
+
+    /// Drop background cells from either end.
+    fn trim(&mut self) {
+        while self.cells.last() == Some(&self.background) {
+            self.cells.pop();
+        }
+        let leading = self
+            .cells
+            .iter()
+            .take_while(|c| **c == self.background)
+            .count();
+        self.cells.drain(..leading);
+        self.offset += leading as i64;
+    }
+
+    pub fn step(&mut self) {
+        let radius = self.radius as i64;
+        let mut window = Vec::with_capacity(2 * self.radius + 1);
+
+        // The pattern can spread by up to one radius in each direction.
+        self.next.clear();
+        for centre in -radius..(self.cells.len() as i64 + radius) {
+            window.clear();
+            for index in (centre - radius)..=(centre + radius) {
+                let cell = if index < 0 {
+                    &self.background
+                } else {
+                    self.cells.get(index as usize).unwrap_or(&self.background)
+                };
+                window.push(cell.clone());
+            }
+            self.next.push((self.rule)(&window));
+        }
+
+        mem::swap(&mut self.cells, &mut self.next);
+        self.offset -= radius;
+        self.generation += 1;
+        self.trim();
+    }
+
+    /// Step through generations until reaching `target`, skipping
+    /// ahead once the line repeats an earlier generation, even if the
+    /// pattern has moved along the line since then.
+    pub fn advance_to(&mut self, target: u64) {
+        assert!(
+            target >= self.generation,
+            "Can't go back to generation {} from {}",
+            target,
+            self.generation
+        );
+
+        let start = self.generation;
+        let mut history = History::new();
+        let mut offsets = vec![self.offset];
+        history.record(self.cells.clone());
+
+        while self.generation < target {
+            self.step();
+            offsets.push(self.offset);
+
+            if let Some(earlier) = history.record(self.cells.clone()) {
+                let jump = Jump::new(start, earlier, self.generation, target);
+                let drift = self.offset - offsets[jump.earlier];
+                self.cells = history.get(jump.state).clone();
+                self.offset = offsets[jump.state] + drift * jump.cycles as i64;
+                self.generation = target;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::BoundingBox;
+
+    fn life(alive: &bool, neighbours: &[bool]) -> bool {
+        match neighbours.iter().filter(|n| **n).count() {
+            3 => true,
+            2 => *alive,
+            _ => false,
+        }
+    }
+
+    fn blinker() -> Grid<bool> {
+        Grid::from_fn(BoundingBox::new(0, 4, 0, 4), |p| {
+            p.y == 2 && p.x > 0 && p.x < 4
+        })
+    }
+
+    #[test]
+    fn oscillator() {
+        let mut automaton = Automaton::new(blinker(), false, life);
+        automaton.step();
+        let vertical = automaton.grid().clone();
+        assert!(vertical[Point::new(2, 1)] && !vertical[Point::new(1, 2)]);
+
+        automaton.step();
+        assert_eq!(automaton.grid(), &blinker());
+
+        automaton.advance_to(1_000_000_001);
+        assert_eq!(automaton.generation(), 1_000_000_001);
+        assert_eq!(automaton.grid(), &vertical);
+    }
+
+    #[test]
+    fn jump_matches_stepping() {
+        // Cells become the count of their living neighbours, mod 3,
+        // which settles into a cycle after a few generations.
+        let grid = Grid::from_fn(BoundingBox::new(0, 3, 0, 2), |p| (p.x * p.y % 3) as u8);
+        let rule = |_: &u8, neighbours: &[u8]| {
+            (neighbours.iter().map(|&n| u32::from(n)).sum::<u32>() % 3) as u8
+        };
+
+        for target in 0..40 {
+            let mut stepped =
+                Automaton::new(grid.clone(), 1, rule).with_neighbourhood(Neighbourhood::VonNeumann);
+            for _ in 0..target {
+                stepped.step();
+            }
+
+            let mut jumped =
+                Automaton::new(grid.clone(), 1, rule).with_neighbourhood(Neighbourhood::VonNeumann);
+            jumped.advance_to(target);
+            assert_eq!(jumped.grid(), stepped.grid(), "Generation {}", target);
+        }
+    }
+
+    #[test]
+    fn line_background_changes() {
+        assert_eq!(
+            LineAutomaton::new(vec![true], false, 1, |w: &[bool]| !w[1]).err(),
+            Some(AutomatonError::BackgroundChanges)
+        );
+    }
+
+    #[test]
+    fn line_growth() {
+        // Rule 90: each cell is the exclusive or of its neighbours.
+        let mut line =
+            LineAutomaton::new(vec![false, true, false], false, 1, |w: &[bool]| w[0] ^ w[2])
+                .unwrap()
+                .with_offset(10);
+        assert_eq!(line.offset(), 11);
+        assert_eq!(line.cells(), &[true]);
+
+        line.step();
+        line.step();
+        assert_eq!(line.offset(), 9);
+        assert_eq!(line.cells(), &[true, false, false, false, true]);
+        assert!(*line.get(13));
+        assert!(!*line.get(100));
+        assert_eq!(
+            line.iter()
+                .filter(|(_, c)| **c)
+                .map(|(p, _)| p)
+                .collect::<Vec<_>>(),
+            vec![9, 13]
+        );
+    }
+
+    #[test]
+    fn line_drift() {
+        // A glider which moves two cells right every generation.
+        let mut line =
+            LineAutomaton::new(vec![true, true, false, true], false, 2, |w: &[bool]| w[0]).unwrap();
+        line.advance_to(50_000_000_000);
+        assert_eq!(line.cells(), &[true, true, false, true]);
+        assert_eq!(line.offset(), 100_000_000_000);
+        assert_eq!(line.generation(), 50_000_000_000);
+    }
+}
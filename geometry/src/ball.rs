@@ -0,0 +1,402 @@
+//! Balls of points within a Manhattan distance of a center.
+//!
+//! In three dimensions these are octahedra, bounded by four pairs of
+//! planes, so rotating the coordinates turns each ball into a box in
+//! four dimensions. Finding the point inside the most balls uses a
+//! priority subdivision of space, which is exact, and uses the faces of
+//! the balls to bound how near each part of space could be.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::space::{Bounds, Coordinates, Point3D, Point4D};
+use crate::Position;
+
+/// Every point within `radius` of `center`, by Manhattan distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ball<P> {
+    center: P,
+    radius: Position,
+}
+
+impl<P: Coordinates> Ball<P> {
+    pub fn new(center: P, radius: Position) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn center(&self) -> P {
+        self.center
+    }
+
+    pub fn radius(&self) -> Position {
+        self.radius
+    }
+
+    pub fn contains(&self, point: P) -> bool {
+        self.center.manhattan_distance(point) <= self.radius
+    }
+
+    /// Whether the balls share at least one point.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.center.manhattan_distance(other.center) <= self.radius + other.radius
+    }
+
+    /// Whether every point of `other` is inside this ball.
+    pub fn contains_ball(&self, other: &Self) -> bool {
+        self.center.manhattan_distance(other.center) + other.radius <= self.radius
+    }
+
+    /// Whether the ball shares at least one point with the bounds.
+    pub fn touches(&self, bounds: &Bounds<P>) -> bool {
+        !bounds.is_empty() && bounds.manhattan_distance(self.center) <= self.radius
+    }
+
+    /// Whether every point of the bounds is inside the ball.
+    pub fn covers(&self, bounds: &Bounds<P>) -> bool {
+        let (min, max) = (bounds.min(), bounds.max());
+        let furthest: Position = (0..P::DIMENSIONS)
+            .map(|axis| {
+                let c = self.center.coordinate(axis);
+                (c - min.coordinate(axis))
+                    .abs()
+                    .max((max.coordinate(axis) - c).abs())
+            })
+            .sum();
+        furthest <= self.radius
+    }
+
+    /// The smallest axis aligned bounds containing the ball.
+    pub fn bounds(&self) -> Bounds<P> {
+        let (center, radius) = (self.center, self.radius);
+        Bounds::from_corners(
+            P::from_fn(|axis| center.coordinate(axis) - radius),
+            P::from_fn(|axis| center.coordinate(axis) + radius),
+        )
+    }
+}
+
+/// Rotate a point onto the normals of the faces of an octahedron, as
+/// `(x + y + z, x + y - z, x - y + z, -x + y + z)`.
+///
+/// Only points where the first coordinate is the sum of the other three
+/// come from a point in three dimensions.
+pub fn rotate(point: Point3D) -> Point4D {
+    let Point3D { x, y, z } = point;
+    Point4D::new(x + y + z, x + y - z, x - y + z, -x + y + z)
+}
+
+impl Ball<Point3D> {
+    /// The ball as a box in rotated coordinates, which contains the
+    /// rotation of every point in the ball.
+    pub fn rotated(&self) -> Bounds<Point4D> {
+        Ball::new(rotate(self.center), self.radius).bounds()
+    }
+
+    /// The rotated box shared by two balls. This is empty when the balls
+    /// don't intersect, but may contain points which aren't rotations of
+    /// any point in three dimensions.
+    pub fn rotated_intersection(&self, other: &Self) -> Option<Bounds<Point4D>> {
+        self.rotated().intersection(&other.rotated())
+    }
+}
+
+/// The point inside the most balls, found by `max_overlap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overlap<P> {
+    point: P,
+    count: usize,
+    distance: Position,
+}
+
+impl<P: Copy> Overlap<P> {
+    pub fn point(&self) -> P {
+        self.point
+    }
+
+    /// The number of balls containing the point.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The distance from the point to the search target.
+    pub fn distance(&self) -> Position {
+        self.distance
+    }
+}
+
+/// The sign vectors normal to each pair of opposite faces of a ball,
+/// with a positive first entry. The Manhattan length of a vector is its
+/// largest dot product with one of these, ignoring signs.
+fn faces<P: Coordinates>() -> impl Iterator<Item = P> {
+    (0..1 << (P::DIMENSIONS - 1)).map(|face: usize| {
+        P::from_fn(|axis| {
+            if axis > 0 && face & (1 << (axis - 1)) != 0 {
+                -1
+            } else {
+                1
+            }
+        })
+    })
+}
+
+fn dot<P: Coordinates>(a: P, b: P) -> Position {
+    (0..P::DIMENSIONS)
+        .map(|axis| a.coordinate(axis) * b.coordinate(axis))
+        .sum()
+}
+
+/// A region of the search, where no point is inside more than `count`
+/// balls, or nearer than `distance` to the target if it is inside that
+/// many.
+#[derive(Debug, PartialEq, Eq)]
+struct Cell<P> {
+    bounds: Bounds<P>,
+    count: usize,
+    distance: Position,
+}
+
+impl<P: Coordinates> Cell<P> {
+    fn new(bounds: Bounds<P>, balls: &[Ball<P>], target: P) -> Self {
+        let touching: Vec<_> = balls.iter().filter(|b| b.touches(&bounds)).collect();
+        let distance = bounds.manhattan_distance(target);
+
+        // Points inside every touching ball lie between the faces of each
+        // one, which bounds their distance much better than the cell does
+        // when the faces are parallel to those around the target.
+        let mut nearest = distance;
+        for face in faces::<P>() {
+            let (mut low, mut high) = (0, 0);
+            for axis in 0..P::DIMENSIONS {
+                let (a, b) = (
+                    face.coordinate(axis) * bounds.min().coordinate(axis),
+                    face.coordinate(axis) * bounds.max().coordinate(axis),
+                );
+                low += a.min(b);
+                high += a.max(b);
+            }
+            for ball in &touching {
+                let center = dot(face, ball.center);
+                low = low.max(center - ball.radius);
+                high = high.min(center + ball.radius);
+            }
+
+            // No point is inside all of them, so one fewer is the best
+            // possible.
+            if low > high {
+                return Self {
+                    bounds,
+                    count: touching.len() - 1,
+                    distance,
+                };
+            }
+
+            let t = dot(face, target);
+            nearest = nearest.max((low - t).max(t - high));
+        }
+
+        Self {
+            bounds,
+            count: touching.len(),
+            distance: nearest,
+        }
+    }
+
+    /// Split the cell in half along every axis which is more than a
+    /// single point wide.
+    fn split(&self) -> Vec<Bounds<P>> {
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        let mid = P::from_fn(|axis| {
+            min.coordinate(axis) + (max.coordinate(axis) - min.coordinate(axis)) / 2
+        });
+        let upper = |half: usize, axis: usize| half & (1 << axis) != 0;
+
+        (0..1 << P::DIMENSIONS)
+            .filter(|&half| {
+                (0..P::DIMENSIONS)
+                    .all(|axis| !upper(half, axis) || mid.coordinate(axis) < max.coordinate(axis))
+            })
+            .map(|half| {
+                Bounds::from_corners(
+                    P::from_fn(|axis| {
+                        if upper(half, axis) {
+                            mid.coordinate(axis) + 1
+                        } else {
+                            min.coordinate(axis)
+                        }
+                    }),
+                    P::from_fn(|axis| {
+                        if upper(half, axis) {
+                            max.coordinate(axis)
+                        } else {
+                            mid.coordinate(axis)
+                        }
+                    }),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Cells with the highest count come first, then those nearest the target,
+/// then the narrowest, so the search goes deep quickly. The first single
+/// point found can't be beaten by any point in another cell.
+impl<P: Coordinates> Ord for Cell<P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.count
+            .cmp(&other.count)
+            .then_with(|| other.distance.cmp(&self.distance))
+            .then_with(|| other.bounds.longest_side().cmp(&self.bounds.longest_side()))
+            .then_with(|| other.bounds.min().cmp(&self.bounds.min()))
+            .then_with(|| other.bounds.max().cmp(&self.bounds.max()))
+    }
+}
+
+impl<P: Coordinates> PartialOrd for Cell<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Find the point inside the most balls, choosing one nearest to `target`
+/// when there are several.
+pub fn max_overlap<P: Coordinates>(balls: &[Ball<P>], target: P) -> Option<Overlap<P>> {
+    let mut bounds = Bounds::empty();
+    for ball in balls {
+        bounds.include(ball.bounds().min());
+        bounds.include(ball.bounds().max());
+    }
+    if bounds.is_empty() {
+        return None;
+    }
+
+    let mut queue = BinaryHeap::new();
+    queue.push(Cell::new(bounds, balls, target));
+
+    while let Some(cell) = queue.pop() {
+        if cell.bounds.min() == cell.bounds.max() {
+            return Some(Overlap {
+                point: cell.bounds.min(),
+                count: cell.count,
+                distance: cell.distance,
+            });
+        }
+        for child in cell.split() {
+            queue.push(Cell::new(child, balls, target));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Point;
+
+    #[test]
+    fn containment() {
+        let ball = Ball::new(Point3D::new(0, 0, 0), 4);
+        assert!(ball.contains(Point3D::new(1, -2, 1)));
+        assert!(!ball.contains(Point3D::new(1, -2, 2)));
+
+        let small = Ball::new(Point3D::new(1, 1, 0), 2);
+        assert!(ball.intersects(&small));
+        assert!(ball.contains_ball(&small));
+        assert!(!small.contains_ball(&ball));
+        assert!(!ball.intersects(&Ball::new(Point3D::new(5, 0, 1), 1)));
+
+        let bounds = Bounds::from_corners(Point3D::new(3, 1, 1), Point3D::new(5, 5, 5));
+        assert!(!ball.touches(&bounds));
+        assert!(Ball::new(Point3D::new(0, 0, 0), 5).touches(&bounds));
+        assert!(ball.covers(&Bounds::from_corners(
+            Point3D::new(-1, -1, -1),
+            Point3D::new(1, 1, 1)
+        )));
+        assert!(!ball.covers(&Bounds::from_corners(
+            Point3D::new(-1, -1, -1),
+            Point3D::new(2, 2, 1)
+        )));
+    }
+
+    #[test]
+    fn rotation() {
+        let ball = Ball::new(Point3D::new(1, 2, 3), 2);
+        let rotated = ball.rotated();
+        for point in ball.bounds().points() {
+            assert_eq!(ball.contains(point), rotated.contains(rotate(point)));
+        }
+
+        let other = Ball::new(Point3D::new(4, 2, 3), 1);
+        assert!(ball.rotated_intersection(&other).is_some());
+        let apart = Ball::new(Point3D::new(5, 2, 3), 1);
+        assert!(!ball.intersects(&apart));
+        assert!(ball.rotated_intersection(&apart).is_none());
+    }
+
+    #[test]
+    fn search() {
+        let balls: Vec<_> = vec![
+            ((10, 12, 12), 2),
+            ((12, 14, 12), 2),
+            ((16, 12, 12), 4),
+            ((14, 14, 14), 6),
+            ((50, 50, 50), 200),
+            ((10, 10, 10), 5),
+        ]
+        .into_iter()
+        .map(|((x, y, z), r)| Ball::new(Point3D::new(x, y, z), r))
+        .collect();
+
+        let best = max_overlap(&balls, Point3D::origin()).unwrap();
+        assert_eq!(best.point(), Point3D::new(12, 12, 12));
+        assert_eq!(best.count(), 5);
+        assert_eq!(best.distance(), 36);
+
+        // Agrees with checking every point, in two dimensions.
+        let balls: Vec<_> = vec![((0, 0), 3), ((4, 1), 2), ((2, 3), 2), ((-3, 2), 4)]
+            .into_iter()
+            .map(|((x, y), r)| Ball::new(Point::new(x, y), r))
+            .collect();
+        let target = Point::new(-1, 5);
+        let best = max_overlap(&balls, target).unwrap();
+        let count = |p: Point| balls.iter().filter(|b| b.contains(p)).count();
+        let expected = Bounds::from_corners(Point::new(-10, -10), Point::new(10, 10))
+            .points()
+            .map(|p| (count(p), -Coordinates::manhattan_distance(p, target)))
+            .max()
+            .unwrap();
+        assert_eq!(count(best.point()), best.count());
+        assert_eq!((best.count(), -best.distance()), expected);
+
+        assert_eq!(max_overlap::<Point3D>(&[], Point3D::origin()), None);
+    }
+
+    #[test]
+    fn search_large() {
+        // Eight balls from every diagonal direction meet at a single point,
+        // among small decoys spread over a space the size of a day 23 input.
+        let meet = Point3D::new(120_000_000, -90_000_000, 40_000_000);
+        let (a, b, c) = (50_000_000, 30_000_000, 40_000_000);
+        let mut balls = Vec::new();
+        for sx in &[-1, 1] {
+            for sy in &[-1, 1] {
+                for sz in &[-1, 1] {
+                    let center = Point3D::new(meet.x + sx * a, meet.y + sy * b, meet.z + sz * c);
+                    balls.push(Ball::new(center, a + b + c));
+                }
+            }
+        }
+        for i in -5..5 {
+            for j in -5..5 {
+                let center = Point3D::new(i * 30_000_000, j * 30_000_000, (i - j) * 10_000_000);
+                balls.push(Ball::new(center, 1_000_000));
+            }
+        }
+
+        let best = max_overlap(&balls, Point3D::origin()).unwrap();
+        assert_eq!(best.point(), meet);
+        assert_eq!(best.count(), 8);
+        assert_eq!(best.distance(), 250_000_000);
+    }
+}
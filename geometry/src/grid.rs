@@ -0,0 +1,377 @@
+//! A dense grid of values, stored in reading order.
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use failure::Fail;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::transform::Transform;
+use crate::{BoundingBox, Point, Position};
+
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum GridError {
+    #[fail(display = "Point {} is outside the grid", _0)]
+    OutOfBounds(Point),
+}
+
+/// A value for every point in a bounding box, backed by a single `Vec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    bbox: BoundingBox,
+    cells: Vec<T>,
+}
+
+fn dimension(size: Position) -> usize {
+    if size < 0 {
+        0
+    } else {
+        size as usize
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// A grid covering `bbox`, with every point set to `fill`.
+    pub fn new(bbox: BoundingBox, fill: T) -> Self {
+        let size = dimension(bbox.width()) * dimension(bbox.height());
+        Self {
+            bbox,
+            cells: vec![fill; size],
+        }
+    }
+
+    /// Change the area covered by the grid, keeping the values of points
+    /// inside both the old and new areas, and setting new points to `fill`.
+    pub fn resize(&mut self, bbox: BoundingBox, fill: T) {
+        let mut grid = Self::new(bbox, fill);
+        for (point, value) in self.iter() {
+            if let Some(i) = grid.offset(point) {
+                grid.cells[i] = value.clone();
+            }
+        }
+        *self = grid;
+    }
+
+    /// Rotate or reflect the grid about the origin.
+    pub fn transform(&self, transform: Transform) -> Self {
+        let inverse = transform.inverse();
+        Self::from_fn(transform.apply_bbox(self.bbox), |p| {
+            self[inverse.apply(p)].clone()
+        })
+    }
+
+    /// The part of the grid inside `bbox`, which is empty if they
+    /// don't overlap.
+    pub fn crop(&self, bbox: BoundingBox) -> Self {
+        let area = self
+            .bbox
+            .intersection(&bbox)
+            .unwrap_or_else(BoundingBox::empty);
+        Self::from_fn(area, |p| self[p].clone())
+    }
+}
+
+impl<T> Grid<T> {
+    /// A grid covering `bbox`, with each point set by `f`.
+    pub fn from_fn<F>(bbox: BoundingBox, f: F) -> Self
+    where
+        F: FnMut(Point) -> T,
+    {
+        Self {
+            bbox,
+            cells: bbox.points().map(f).collect(),
+        }
+    }
+
+    /// A grid covering `bbox`, with the points set by `f` in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn par_from_fn<F>(bbox: BoundingBox, f: F) -> Self
+    where
+        T: Send,
+        F: Fn(Point) -> T + Sync + Send,
+    {
+        Self {
+            bbox,
+            cells: bbox.par_points().map(f).collect(),
+        }
+    }
+
+    /// A grid from values already in reading order.
+    pub(crate) fn from_cells(bbox: BoundingBox, cells: Vec<T>) -> Self {
+        debug_assert_eq!(
+            cells.len(),
+            dimension(bbox.width()) * dimension(bbox.height())
+        );
+        Self { bbox, cells }
+    }
+
+    pub fn bbox(&self) -> BoundingBox {
+        self.bbox
+    }
+
+    /// Move every value by the same offset.
+    pub fn translate(self, dx: Position, dy: Position) -> Self {
+        Self {
+            bbox: self.bbox.translate(dx, dy),
+            cells: self.cells,
+        }
+    }
+
+    /// Move the grid so that its top left corner is at the origin.
+    pub fn normalize(self) -> Self {
+        let origin = self.bbox.origin();
+        self.translate(-origin.x, -origin.y)
+    }
+
+    pub fn width(&self) -> usize {
+        dimension(self.bbox.width())
+    }
+
+    pub fn height(&self) -> usize {
+        dimension(self.bbox.height())
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.offset(point).is_some()
+    }
+
+    fn offset(&self, point: Point) -> Option<usize> {
+        if self.cells.is_empty() || !self.bbox.contains(point) {
+            return None;
+        }
+        let x = (point.x - self.bbox.left()) as usize;
+        let y = (point.y - self.bbox.top()) as usize;
+        Some(y * self.width() + x)
+    }
+
+    fn point(&self, offset: usize) -> Point {
+        Point::new(
+            self.bbox.left() + (offset % self.width()) as Position,
+            self.bbox.top() + (offset / self.width()) as Position,
+        )
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.offset(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        match self.offset(point) {
+            Some(i) => Some(&mut self.cells[i]),
+            None => None,
+        }
+    }
+
+    /// Set the value at a point, returning the previous value.
+    pub fn set(&mut self, point: Point, value: T) -> Result<T, GridError> {
+        let cell = self.get_mut(point).ok_or(GridError::OutOfBounds(point))?;
+        Ok(std::mem::replace(cell, value))
+    }
+
+    /// Points and values in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.bbox.points().zip(self.cells.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Point, &mut T)> {
+        self.bbox.points().zip(self.cells.iter_mut())
+    }
+
+    /// Values in reading order.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    /// The points adjacent to `point` which are inside the grid,
+    /// in reading order.
+    pub fn neighbours(&self, point: Point) -> impl Iterator<Item = (Point, &T)> {
+        point
+            .adjacent()
+            .filter_map(move |p| self.get(p).map(|v| (p, v)))
+    }
+
+    /// The points adjacent to `point`, including diagonally,
+    /// which are inside the grid.
+    pub fn neighbours_diagonal(&self, point: Point) -> impl Iterator<Item = (Point, &T)> {
+        point
+            .adjacent_diagonal()
+            .filter_map(move |p| self.get(p).map(|v| (p, v)))
+    }
+
+    pub fn row(&self, y: Position) -> Option<&[T]> {
+        let start = self.offset(Point::new(self.bbox.left(), y))?;
+        Some(&self.cells[start..start + self.width()])
+    }
+
+    pub fn column(&self, x: Position) -> Option<impl Iterator<Item = &T>> {
+        let start = self.offset(Point::new(x, self.bbox.top()))?;
+        Some(self.cells[start..].iter().step_by(self.width()))
+    }
+
+    /// Each row, from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // Chunks panics on a zero size, even when there are no cells.
+        self.cells.chunks(self.width().max(1))
+    }
+
+    /// Each column, from left to right.
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        self.bbox.horizontal().filter_map(move |x| self.column(x))
+    }
+
+    pub fn map<U, F>(&self, mut f: F) -> Grid<U>
+    where
+        F: FnMut(Point, &T) -> U,
+    {
+        Grid {
+            bbox: self.bbox,
+            cells: self.iter().map(|(p, v)| f(p, v)).collect(),
+        }
+    }
+
+    /// Find the first point, in reading order, whose value matches.
+    pub fn position<F>(&self, f: F) -> Option<Point>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.cells.iter().position(f).map(|i| self.point(i))
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        match self.get(point) {
+            Some(v) => v,
+            None => panic!("Point {} is outside the grid", point),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        match self.offset(point) {
+            Some(i) => &mut self.cells[i],
+            None => panic!("Point {} is outside the grid", point),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for value in row {
+                write!(f, "{}", value)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Grid<i32> {
+        Grid::from_fn(BoundingBox::new(1, 3, -1, 0), |p| p.x * 10 + p.y)
+    }
+
+    #[test]
+    fn addressing() {
+        let mut grid = example();
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 2);
+
+        assert_eq!(grid.get(Point::new(2, -1)), Some(&19));
+        assert_eq!(grid[Point::new(3, 0)], 30);
+        assert_eq!(grid.get(Point::new(0, 0)), None);
+        assert_eq!(grid.get(Point::new(1, 1)), None);
+
+        assert_eq!(grid.set(Point::new(1, 0), 5), Ok(10));
+        assert_eq!(grid[Point::new(1, 0)], 5);
+        assert_eq!(
+            grid.set(Point::new(4, 0), 5),
+            Err(GridError::OutOfBounds(Point::new(4, 0)))
+        );
+
+        assert_eq!(grid.position(|v| *v == 29), Some(Point::new(3, -1)));
+        assert_eq!(grid.to_string(), "91929\n52030\n");
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let grid = example();
+        assert_eq!(grid.row(0), Some(&[10, 20, 30][..]));
+        assert_eq!(grid.row(1), None);
+        assert_eq!(
+            grid.rows().collect::<Vec<_>>(),
+            vec![&[9, 19, 29][..], &[10, 20, 30][..]]
+        );
+        assert_eq!(
+            grid.column(2).unwrap().cloned().collect::<Vec<_>>(),
+            vec![19, 20]
+        );
+        assert_eq!(
+            grid.columns()
+                .map(|c| c.cloned().collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            vec![vec![9, 10], vec![19, 20], vec![29, 30]]
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = example();
+        assert_eq!(
+            grid.neighbours(Point::new(1, 0)).collect::<Vec<_>>(),
+            vec![(Point::new(1, -1), &9), (Point::new(2, 0), &20)]
+        );
+        assert_eq!(grid.neighbours_diagonal(Point::new(2, 0)).count(), 5);
+    }
+
+    #[test]
+    fn resize() {
+        let mut grid = example();
+        grid.resize(BoundingBox::new(2, 4, 0, 1), 0);
+        assert_eq!(grid.to_string(), "20300\n000\n");
+
+        grid.resize(BoundingBox::empty(), 0);
+        assert!(grid.is_empty());
+        assert_eq!(grid.rows().count(), 0);
+        assert_eq!(grid.get(Point::new(2, 0)), None);
+    }
+
+    #[test]
+    fn transforms() {
+        let grid = example();
+        let rotated = grid.transform(Transform::RotateRight);
+        assert_eq!(rotated.bbox(), BoundingBox::new(0, 1, 1, 3));
+        assert_eq!(rotated.to_string(), "109\n2019\n3029\n");
+        assert_eq!(rotated.transform(Transform::RotateLeft), grid);
+
+        let flipped = grid.transform(Transform::FlipHorizontal).normalize();
+        assert_eq!(flipped.bbox(), BoundingBox::new(0, 2, 0, 1));
+        assert_eq!(flipped.to_string(), "29199\n302010\n");
+
+        let moved = grid.clone().translate(2, 3);
+        assert_eq!(moved[Point::new(3, 2)], 9);
+        assert_eq!(moved.normalize(), grid.clone().normalize());
+
+        let cropped = grid.crop(BoundingBox::new(2, 5, 0, 4));
+        assert_eq!(cropped.bbox(), BoundingBox::new(2, 3, 0, 0));
+        assert_eq!(cropped.to_string(), "2030\n");
+        assert!(grid.crop(BoundingBox::new(5, 6, 5, 6)).is_empty());
+    }
+}
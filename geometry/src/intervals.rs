@@ -0,0 +1,215 @@
+//! Sets of integers, stored as ranges rather than one value at a time.
+
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+
+use crate::Position;
+
+/// A set of positions, kept as sorted ranges which neither overlap nor
+/// touch, so that each set has exactly one representation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IntervalSet {
+    ranges: Vec<(Position, Position)>,
+}
+
+/// Sort ranges and merge any which overlap or touch, dropping empty ones.
+fn merge(ranges: &mut Vec<(Position, Position)>) {
+    ranges.retain(|(start, end)| start <= end);
+    ranges.sort();
+
+    let mut merged = 0;
+    for i in 0..ranges.len() {
+        let (start, end) = ranges[i];
+        if merged > 0 && i64::from(start) <= i64::from(ranges[merged - 1].1) + 1 {
+            ranges[merged - 1].1 = ranges[merged - 1].1.max(end);
+        } else {
+            ranges[merged] = (start, end);
+            merged += 1;
+        }
+    }
+    ranges.truncate(merged);
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, range: RangeInclusive<Position>) {
+        self.ranges.push((*range.start(), *range.end()));
+        merge(&mut self.ranges);
+    }
+
+    pub fn remove(&mut self, range: RangeInclusive<Position>) {
+        *self = self.difference(&Self::from(range));
+    }
+
+    pub fn contains(&self, value: Position) -> bool {
+        self.ranges
+            .binary_search_by(|(start, end)| {
+                if *end < value {
+                    Ordering::Less
+                } else if *start > value {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    /// The number of positions in the set.
+    pub fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|(start, end)| (i64::from(*end) - i64::from(*start) + 1) as u64)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The disjoint ranges making up the set, in ascending order.
+    pub fn ranges<'s>(&'s self) -> impl Iterator<Item = RangeInclusive<Position>> + 's {
+        self.ranges.iter().map(|(start, end)| *start..=*end)
+    }
+
+    /// The smallest range containing the whole set.
+    pub fn span(&self) -> Option<RangeInclusive<Position>> {
+        let first = self.ranges.first()?;
+        let last = self.ranges.last()?;
+        Some(first.0..=last.1)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut ranges: Vec<_> = self.ranges.iter().chain(&other.ranges).cloned().collect();
+        merge(&mut ranges);
+        Self { ranges }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (self.ranges[i], other.ranges[j]);
+            let (start, end) = (a.0.max(b.0), a.1.min(b.1));
+            if start <= end {
+                ranges.push((start, end));
+            }
+            if a.1 < b.1 {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    /// The positions in this set which aren't in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for &(start, end) in &self.ranges {
+            let (mut start, end) = (i64::from(start), i64::from(end));
+            while j < other.ranges.len() && i64::from(other.ranges[j].1) < start {
+                j += 1;
+            }
+
+            for &(cut_start, cut_end) in &other.ranges[j..] {
+                if i64::from(cut_start) > end {
+                    break;
+                }
+                if i64::from(cut_start) > start {
+                    ranges.push((start as Position, cut_start - 1));
+                }
+                start = start.max(i64::from(cut_end) + 1);
+            }
+            if start <= end {
+                ranges.push((start as Position, end as Position));
+            }
+        }
+        Self { ranges }
+    }
+}
+
+impl From<RangeInclusive<Position>> for IntervalSet {
+    fn from(range: RangeInclusive<Position>) -> Self {
+        Some(range).into_iter().collect()
+    }
+}
+
+impl FromIterator<RangeInclusive<Position>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<Position>>>(iter: I) -> Self {
+        let mut ranges: Vec<_> = iter.into_iter().map(|r| (*r.start(), *r.end())).collect();
+        merge(&mut ranges);
+        Self { ranges }
+    }
+}
+
+impl Extend<RangeInclusive<Position>> for IntervalSet {
+    fn extend<I: IntoIterator<Item = RangeInclusive<Position>>>(&mut self, iter: I) {
+        self.ranges
+            .extend(iter.into_iter().map(|r| (*r.start(), *r.end())));
+        merge(&mut self.ranges);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(ranges: &[(Position, Position)]) -> IntervalSet {
+        ranges.iter().map(|(s, e)| *s..=*e).collect()
+    }
+
+    #[test]
+    fn merging() {
+        let mut s = set(&[(5, 7), (1, 2), (3, 3), (10, 9), (6, 8)]);
+        assert_eq!(s.ranges().collect::<Vec<_>>(), vec![1..=3, 5..=8]);
+        assert_eq!(s.len(), 7);
+        assert_eq!(s.span(), Some(1..=8));
+        assert!(s.contains(3));
+        assert!(!s.contains(4));
+        assert!(!s.contains(9));
+
+        s.insert(4..=4);
+        assert_eq!(s, IntervalSet::from(1..=8));
+        s.remove(3..=5);
+        assert_eq!(s, set(&[(1, 2), (6, 8)]));
+
+        assert!(IntervalSet::new().is_empty());
+        assert_eq!(IntervalSet::new().span(), None);
+        assert_eq!(
+            IntervalSet::from(Position::MAX - 1..=Position::MAX).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn algebra() {
+        let a = set(&[(0, 4), (8, 12), (20, 20)]);
+        let b = set(&[(3, 9), (12, 15)]);
+
+        assert_eq!(a.union(&b), set(&[(0, 15), (20, 20)]));
+        assert_eq!(a.intersection(&b), set(&[(3, 4), (8, 9), (12, 12)]));
+        assert_eq!(a.difference(&b), set(&[(0, 2), (10, 11), (20, 20)]));
+        assert_eq!(b.difference(&a), set(&[(5, 7), (13, 15)]));
+
+        for value in -2..25 {
+            assert_eq!(
+                a.union(&b).contains(value),
+                a.contains(value) || b.contains(value)
+            );
+            assert_eq!(
+                a.intersection(&b).contains(value),
+                a.contains(value) && b.contains(value)
+            );
+            assert_eq!(
+                a.difference(&b).contains(value),
+                a.contains(value) && !b.contains(value)
+            );
+        }
+    }
+}
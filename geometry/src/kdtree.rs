@@ -0,0 +1,382 @@
+//! A KD-tree for finding points near to others by Manhattan distance.
+
+use std::collections::BinaryHeap;
+use std::iter::FromIterator;
+
+use crate::space::Coordinates;
+use crate::Position;
+
+#[derive(Debug, Clone)]
+struct Node<P, T> {
+    point: P,
+    value: T,
+    axis: usize,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/// The points nearest to a location, all at the same distance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nearest<'t, P, T> {
+    distance: Position,
+    items: Vec<(&'t P, &'t T)>,
+}
+
+impl<'t, P: Ord, T> Nearest<'t, P, T> {
+    pub fn distance(&self) -> Position {
+        self.distance
+    }
+
+    /// Every nearest point, in point order.
+    pub fn items(&self) -> &[(&'t P, &'t T)] {
+        &self.items
+    }
+
+    pub fn is_tied(&self) -> bool {
+        self.items.len() > 1
+    }
+
+    /// The nearest point, unless several are equally near.
+    pub fn unique(&self) -> Option<(&'t P, &'t T)> {
+        if self.is_tied() {
+            None
+        } else {
+            self.items.first().cloned()
+        }
+    }
+}
+
+/// Gathers points during a search, and limits how far it needs to look.
+trait Collector<P> {
+    /// Points further than this can't be collected.
+    fn bound(&self) -> Position;
+
+    fn offer(&mut self, distance: Position, point: P, index: usize);
+}
+
+struct NearestCollector {
+    distance: Position,
+    indices: Vec<usize>,
+}
+
+impl<P> Collector<P> for NearestCollector {
+    fn bound(&self) -> Position {
+        self.distance
+    }
+
+    fn offer(&mut self, distance: Position, _point: P, index: usize) {
+        if distance < self.distance {
+            self.distance = distance;
+            self.indices.clear();
+        }
+        if distance == self.distance {
+            self.indices.push(index);
+        }
+    }
+}
+
+struct RadiusCollector {
+    radius: Position,
+    indices: Vec<usize>,
+}
+
+impl<P> Collector<P> for RadiusCollector {
+    fn bound(&self) -> Position {
+        self.radius
+    }
+
+    fn offer(&mut self, distance: Position, _point: P, index: usize) {
+        if distance <= self.radius {
+            self.indices.push(index);
+        }
+    }
+}
+
+struct KNearestCollector<P> {
+    k: usize,
+    heap: BinaryHeap<(Position, P, usize)>,
+}
+
+impl<P: Ord> Collector<P> for KNearestCollector<P> {
+    fn bound(&self) -> Position {
+        if self.heap.len() < self.k {
+            Position::MAX
+        } else {
+            self.heap.peek().map_or(Position::MAX, |e| e.0)
+        }
+    }
+
+    fn offer(&mut self, distance: Position, point: P, index: usize) {
+        self.heap.push((distance, point, index));
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+}
+
+/// A balanced KD-tree of points, each with an associated value.
+#[derive(Debug, Clone)]
+pub struct KDTree<P, T> {
+    nodes: Vec<Node<P, T>>,
+    root: Option<usize>,
+}
+
+impl<P: Coordinates> KDTree<P, ()> {
+    pub fn from_points<I: IntoIterator<Item = P>>(points: I) -> Self {
+        points.into_iter().map(|p| (p, ())).collect()
+    }
+}
+
+impl<P: Coordinates, T> FromIterator<(P, T)> for KDTree<P, T> {
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<P: Coordinates, T> KDTree<P, T> {
+    pub fn new(items: Vec<(P, T)>) -> Self {
+        let mut nodes = Vec::with_capacity(items.len());
+        let root = Self::build(items, 0, &mut nodes);
+        Self { nodes, root }
+    }
+
+    fn build(mut items: Vec<(P, T)>, depth: usize, nodes: &mut Vec<Node<P, T>>) -> Option<usize> {
+        if items.is_empty() {
+            return None;
+        }
+
+        let axis = depth % P::DIMENSIONS;
+        items.sort_by(|a, b| {
+            a.0.coordinate(axis)
+                .cmp(&b.0.coordinate(axis))
+                .then(a.0.cmp(&b.0))
+        });
+
+        let midpoint = items.len() / 2;
+        let right = items.split_off(midpoint + 1);
+        let (point, value) = items.pop().expect("Midpoint is in range");
+
+        let left = Self::build(items, depth + 1, nodes);
+        let right = Self::build(right, depth + 1, nodes);
+
+        nodes.push(Node {
+            point,
+            value,
+            axis,
+            left,
+            right,
+        });
+        Some(nodes.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Every point and value, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &T)> {
+        self.nodes.iter().map(|n| (&n.point, &n.value))
+    }
+
+    fn search<C: Collector<P>>(&self, node: Option<usize>, location: P, collector: &mut C) {
+        let index = match node {
+            Some(index) => index,
+            None => return,
+        };
+        let node = &self.nodes[index];
+
+        collector.offer(location.manhattan_distance(node.point), node.point, index);
+
+        let difference = location.coordinate(node.axis) - node.point.coordinate(node.axis);
+        let (near, far) = if difference < 0 {
+            (node.left, node.right)
+        } else {
+            (node.right, node.left)
+        };
+
+        self.search(near, location, collector);
+
+        // Every point on the far side is at least this far away.
+        if difference.abs() <= collector.bound() {
+            self.search(far, location, collector);
+        }
+    }
+
+    fn item(&self, index: usize) -> (&P, &T) {
+        let node = &self.nodes[index];
+        (&node.point, &node.value)
+    }
+
+    fn items(&self, mut indices: Vec<usize>) -> Vec<(&P, &T)> {
+        indices.sort_by(|a, b| self.nodes[*a].point.cmp(&self.nodes[*b].point));
+        indices.into_iter().map(|i| self.item(i)).collect()
+    }
+
+    /// The points nearest to `location`, which may be tied.
+    pub fn nearest(&self, location: P) -> Option<Nearest<'_, P, T>> {
+        let mut collector = NearestCollector {
+            distance: Position::MAX,
+            indices: Vec::new(),
+        };
+        self.search(self.root, location, &mut collector);
+
+        if collector.indices.is_empty() {
+            return None;
+        }
+
+        Some(Nearest {
+            distance: collector.distance,
+            items: self.items(collector.indices),
+        })
+    }
+
+    /// The `k` points nearest to `location`, nearest first. Points at
+    /// the same distance are ordered, and chosen, in point order.
+    pub fn k_nearest(&self, location: P, k: usize) -> Vec<(Position, &P, &T)> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut collector = KNearestCollector {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        };
+        self.search(self.root, location, &mut collector);
+
+        collector
+            .heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(d, _, i)| {
+                let (p, v) = self.item(i);
+                (d, p, v)
+            })
+            .collect()
+    }
+
+    /// Every point within `radius` of `location`, in point order.
+    pub fn within(&self, location: P, radius: Position) -> Vec<(&P, &T)> {
+        let mut collector = RadiusCollector {
+            radius,
+            indices: Vec::new(),
+        };
+        self.search(self.root, location, &mut collector);
+        self.items(collector.indices)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::space::Point3D;
+    use crate::Point;
+
+    /// A small linear congruential generator, for repeatable point clouds.
+    fn cloud<P: Coordinates>(seed: u64, n: usize, spread: Position) -> Vec<P> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 33) % (2 * spread as u64 + 1)) as Position - spread
+        };
+        (0..n).map(|_| P::from_fn(|_| next())).collect()
+    }
+
+    fn brute_nearest<P: Coordinates>(points: &[P], location: P) -> (Position, Vec<P>) {
+        let distance = points
+            .iter()
+            .map(|p| p.manhattan_distance(location))
+            .min()
+            .unwrap();
+        let mut nearest: Vec<P> = points
+            .iter()
+            .filter(|p| p.manhattan_distance(location) == distance)
+            .cloned()
+            .collect();
+        nearest.sort();
+        (distance, nearest)
+    }
+
+    fn check_against_brute_force<P: Coordinates>(seed: u64) {
+        let points: Vec<P> = cloud(seed, 200, 20);
+        let tree = KDTree::from_points(points.clone());
+        assert_eq!(tree.len(), points.len());
+
+        for location in cloud::<P>(seed + 1, 50, 25) {
+            let nearest = tree.nearest(location).unwrap();
+            let (distance, expected) = brute_nearest(&points, location);
+            assert_eq!(nearest.distance(), distance);
+            assert_eq!(
+                nearest.items().iter().map(|(p, _)| **p).collect::<Vec<_>>(),
+                expected
+            );
+
+            let mut within: Vec<P> = points
+                .iter()
+                .filter(|p| p.manhattan_distance(location) <= 10)
+                .cloned()
+                .collect();
+            within.sort();
+            assert_eq!(
+                tree.within(location, 10)
+                    .into_iter()
+                    .map(|(p, _)| *p)
+                    .collect::<Vec<_>>(),
+                within
+            );
+
+            let mut sorted: Vec<(Position, P)> = points
+                .iter()
+                .map(|p| (p.manhattan_distance(location), *p))
+                .collect();
+            sorted.sort();
+            sorted.truncate(7);
+            assert_eq!(
+                tree.k_nearest(location, 7)
+                    .into_iter()
+                    .map(|(d, p, _)| (d, *p))
+                    .collect::<Vec<_>>(),
+                sorted
+            );
+        }
+    }
+
+    #[test]
+    fn brute_force_2d() {
+        check_against_brute_force::<Point>(1);
+    }
+
+    #[test]
+    fn brute_force_3d() {
+        check_against_brute_force::<Point3D>(2);
+    }
+
+    #[test]
+    fn ties() {
+        let tree: KDTree<Point, char> = vec![
+            (Point::new(0, 0), 'a'),
+            (Point::new(2, 0), 'b'),
+            (Point::new(5, 5), 'c'),
+        ]
+        .into_iter()
+        .collect();
+
+        let nearest = tree.nearest(Point::new(1, 0)).unwrap();
+        assert!(nearest.is_tied());
+        assert_eq!(nearest.unique(), None);
+        assert_eq!(nearest.distance(), 1);
+
+        let nearest = tree.nearest(Point::new(4, 4)).unwrap();
+        assert_eq!(nearest.unique(), Some((&Point::new(5, 5), &'c')));
+
+        let empty: KDTree<Point, ()> = KDTree::from_points(vec![]);
+        assert!(empty.nearest(Point::new(0, 0)).is_none());
+        assert!(empty.within(Point::new(0, 0), 3).is_empty());
+    }
+}
@@ -6,13 +6,45 @@ use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 use failure::Fail;
-use itertools::iproduct;
 use lazy_static::lazy_static;
 use regex::Regex;
 
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod ascii;
+pub mod automaton;
+pub mod ball;
+pub mod grid;
+pub mod intervals;
+pub mod kdtree;
+pub mod rects;
+pub mod regions;
+pub mod search;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod space;
+pub mod transform;
+pub mod voronoi;
+
+pub use crate::ascii::{MapParser, ParseMapError};
+pub use crate::automaton::{Automaton, AutomatonError, LineAutomaton, Neighbourhood};
+pub use crate::ball::{Ball, Overlap};
+pub use crate::grid::{Grid, GridError};
+pub use crate::intervals::IntervalSet;
+pub use crate::kdtree::{KDTree, Nearest};
+pub use crate::rects::RectangleSet;
+pub use crate::regions::{Components, Region};
+pub use crate::space::{Bounds, Coordinates, Point3D, Point4D};
+pub use crate::transform::Transform;
+pub use crate::voronoi::{Metric, Voronoi};
+
 pub type Position = i32;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Direction {
     Up,
     Down,
@@ -34,6 +66,205 @@ impl Direction {
     pub fn all() -> impl Iterator<Item = Self> {
         DIRECTIONS.iter().cloned()
     }
+
+    /// Turn a quarter turn anticlockwise, as seen on screen.
+    pub fn turn_left(self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    /// Turn a quarter turn clockwise, as seen on screen.
+    pub fn turn_right(self) -> Self {
+        self.turn_left().reverse()
+    }
+
+    pub fn reverse(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// The direction after bouncing off a mirror.
+    pub fn reflect(self, mirror: Mirror) -> Self {
+        match (mirror, self) {
+            (Mirror::Slash, Direction::Up) | (Mirror::Slash, Direction::Down) => self.turn_right(),
+            (Mirror::Slash, _) => self.turn_left(),
+            (Mirror::Backslash, Direction::Up) | (Mirror::Backslash, Direction::Down) => {
+                self.turn_left()
+            }
+            (Mirror::Backslash, _) => self.turn_right(),
+        }
+    }
+}
+
+/// A diagonal mirror, drawn as `/` or `\`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Mirror {
+    Slash,
+    Backslash,
+}
+
+/// The eight points of the compass, where north is up.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Compass {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+const COMPASS: [Compass; 8] = [
+    Compass::NorthWest,
+    Compass::North,
+    Compass::NorthEast,
+    Compass::West,
+    Compass::East,
+    Compass::SouthWest,
+    Compass::South,
+    Compass::SouthEast,
+];
+
+const COMPASS_COLUMNS: [Compass; 8] = [
+    Compass::NorthWest,
+    Compass::West,
+    Compass::SouthWest,
+    Compass::North,
+    Compass::South,
+    Compass::NorthEast,
+    Compass::East,
+    Compass::SouthEast,
+];
+
+impl Compass {
+    /// Enumerates all eight headings in reading order of the
+    /// points they lead to, like `Direction::all`.
+    pub fn all() -> impl Iterator<Item = Self> {
+        COMPASS.iter().cloned()
+    }
+
+    /// Enumerates all eight headings column by column, from the left,
+    /// by the points they lead to.
+    pub fn column_major() -> impl Iterator<Item = Self> {
+        COMPASS_COLUMNS.iter().cloned()
+    }
+
+    /// The change in x and y from one step in this heading.
+    pub fn offset(self) -> (Position, Position) {
+        match self {
+            Compass::North => (0, -1),
+            Compass::NorthEast => (1, -1),
+            Compass::East => (1, 0),
+            Compass::SouthEast => (1, 1),
+            Compass::South => (0, 1),
+            Compass::SouthWest => (-1, 1),
+            Compass::West => (-1, 0),
+            Compass::NorthWest => (-1, -1),
+        }
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        let (x, y) = self.offset();
+        x != 0 && y != 0
+    }
+
+    /// Turn an eighth of a turn anticlockwise.
+    pub fn rotate_left(self) -> Self {
+        match self {
+            Compass::North => Compass::NorthWest,
+            Compass::NorthWest => Compass::West,
+            Compass::West => Compass::SouthWest,
+            Compass::SouthWest => Compass::South,
+            Compass::South => Compass::SouthEast,
+            Compass::SouthEast => Compass::East,
+            Compass::East => Compass::NorthEast,
+            Compass::NorthEast => Compass::North,
+        }
+    }
+
+    /// Turn an eighth of a turn clockwise.
+    pub fn rotate_right(self) -> Self {
+        match self {
+            Compass::North => Compass::NorthEast,
+            Compass::NorthEast => Compass::East,
+            Compass::East => Compass::SouthEast,
+            Compass::SouthEast => Compass::South,
+            Compass::South => Compass::SouthWest,
+            Compass::SouthWest => Compass::West,
+            Compass::West => Compass::NorthWest,
+            Compass::NorthWest => Compass::North,
+        }
+    }
+
+    pub fn reverse(self) -> Self {
+        self.rotate_right()
+            .rotate_right()
+            .rotate_right()
+            .rotate_right()
+    }
+}
+
+impl From<Direction> for Compass {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => Compass::North,
+            Direction::Down => Compass::South,
+            Direction::Left => Compass::West,
+            Direction::Right => Compass::East,
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum ParseCompassError {
+    #[fail(display = "Invalid compass heading: {}", _0)]
+    InvalidHeading(String),
+}
+
+impl FromStr for Compass {
+    type Err = ParseCompassError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "N" => Ok(Compass::North),
+            "NE" => Ok(Compass::NorthEast),
+            "E" => Ok(Compass::East),
+            "SE" => Ok(Compass::SouthEast),
+            "S" => Ok(Compass::South),
+            "SW" => Ok(Compass::SouthWest),
+            "W" => Ok(Compass::West),
+            "NW" => Ok(Compass::NorthWest),
+            _ => Err(ParseCompassError::InvalidHeading(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Compass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Compass::North => "N",
+            Compass::NorthEast => "NE",
+            Compass::East => "E",
+            Compass::SouthEast => "SE",
+            Compass::South => "S",
+            Compass::SouthWest => "SW",
+            Compass::West => "W",
+            Compass::NorthWest => "NW",
+        };
+        write!(f, "{}", s)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -92,10 +323,18 @@ impl Point {
         Direction::all().map(move |d| self.step(d))
     }
 
+    /// Take one step in any of the eight compass headings.
+    pub fn step_compass(self, heading: Compass) -> Self {
+        let (x, y) = heading.offset();
+        Self::new(self.x + x, self.y + y)
+    }
+
     pub fn adjacent_diagonal(self) -> impl Iterator<Item = Self> {
-        iproduct!(-1..2, -1..2)
-            .filter(|(x, y)| !(*x == 0 && *y == 0))
-            .map(move |(x, y)| Point::new(self.x + x, self.y + y))
+        Compass::column_major().map(move |c| self.step_compass(c))
+    }
+
+    pub fn translate(self, dx: Position, dy: Position) -> Self {
+        Self::new(self.x + dx, self.y + dy)
     }
 
     pub fn manhattan_distance(self, other: Point) -> Position {
@@ -246,6 +485,63 @@ impl BoundingBox {
         }
     }
 
+    /// The points inside both boxes, if there are any.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let bbox = Self {
+            left: cmp::max(self.left, other.left),
+            right: cmp::min(self.right, other.right),
+            top: cmp::max(self.top, other.top),
+            bottom: cmp::min(self.bottom, other.bottom),
+        };
+        if bbox.is_empty() {
+            None
+        } else {
+            Some(bbox)
+        }
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The number of points inside both boxes.
+    pub fn overlap_area(&self, other: &Self) -> u64 {
+        self.intersection(other).map_or(0, |bbox| bbox.area())
+    }
+
+    /// The points inside this box but not `other`, as up to four
+    /// disjoint boxes: full width strips above and below `other`,
+    /// then strips to its left and right.
+    pub fn difference(&self, other: &Self) -> Vec<Self> {
+        let overlap = match self.intersection(other) {
+            None if self.is_empty() => return Vec::new(),
+            None => return vec![*self],
+            Some(overlap) => overlap,
+        };
+
+        let pieces = [
+            Self::new(self.left, self.right, self.top, overlap.top - 1),
+            Self::new(self.left, self.right, overlap.bottom + 1, self.bottom),
+            Self::new(self.left, overlap.left - 1, overlap.top, overlap.bottom),
+            Self::new(overlap.right + 1, self.right, overlap.top, overlap.bottom),
+        ];
+        pieces.iter().filter(|b| !b.is_empty()).cloned().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left > self.right || self.top > self.bottom
+    }
+
+    /// The number of points inside the box.
+    pub fn area(&self) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        let width = i64::from(self.right) - i64::from(self.left) + 1;
+        let height = i64::from(self.bottom) - i64::from(self.top) + 1;
+        (width * height) as u64
+    }
+
     pub fn margin(&self, size: Position) -> Self {
         Self {
             left: self.left - size,
@@ -273,6 +569,24 @@ impl BoundingBox {
         }
     }
 
+    /// Move the box, leaving empty boxes unchanged.
+    pub fn translate(&self, dx: Position, dy: Position) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        Self {
+            left: self.left + dx,
+            right: self.right + dx,
+            top: self.top + dy,
+            bottom: self.bottom + dy,
+        }
+    }
+
+    /// The top left corner of the box.
+    pub fn origin(&self) -> Point {
+        Point::new(self.left, self.top)
+    }
+
     pub fn vertical(&self) -> RangeInclusive<Position> {
         self.top..=self.bottom
     }
@@ -319,43 +633,131 @@ impl BoundingBox {
             || point.y == self.bottom
     }
 
+    /// Every point in the box, in reading order.
     pub fn points(&self) -> BoundingBoxIterator {
         BoundingBoxIterator {
-            bbox: self,
-            px: 0,
-            py: 0,
+            bbox: *self,
+            width: self.width().max(1) as u64,
+            front: 0,
+            back: self.area(),
+        }
+    }
+
+    /// Every point in the box, in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn par_points(&self) -> impl ParallelIterator<Item = Point> {
+        let points = self.points();
+        (0..self.area())
+            .into_par_iter()
+            .map(move |i| points.point(i))
+    }
+
+    /// The points along each row, from top to bottom.
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = BoundingBoxIterator> {
+        let bbox = *self;
+        self.vertical()
+            .filter(move |_| !bbox.is_empty())
+            .map(move |y| Self::new(bbox.left, bbox.right, y, y).points())
+    }
+
+    /// The points down each column, from left to right.
+    pub fn columns(&self) -> impl DoubleEndedIterator<Item = BoundingBoxIterator> {
+        let bbox = *self;
+        self.horizontal()
+            .filter(move |_| !bbox.is_empty())
+            .map(move |x| Self::new(x, x, bbox.top, bbox.bottom).points())
+    }
+
+    /// The points along one side of the box, in reading order.
+    pub fn edge(&self, side: Direction) -> BoundingBoxIterator {
+        if self.is_empty() {
+            return self.points();
+        }
+        match side {
+            Direction::Up => Self::new(self.left, self.right, self.top, self.top),
+            Direction::Down => Self::new(self.left, self.right, self.bottom, self.bottom),
+            Direction::Left => Self::new(self.left, self.left, self.top, self.bottom),
+            Direction::Right => Self::new(self.right, self.right, self.top, self.bottom),
         }
+        .points()
     }
+
+    /// Each point on the edge of the box once, clockwise from the top left.
+    pub fn perimeter(&self) -> impl Iterator<Item = Point> {
+        let Self {
+            left,
+            right,
+            top,
+            bottom,
+        } = *self;
+        let mut points: Vec<Point> = self.edge(Direction::Up).collect();
+        points.extend(self.edge(Direction::Right).filter(|p| p.y != top));
+        if bottom != top {
+            points.extend(self.edge(Direction::Down).rev().filter(|p| p.x != right));
+        }
+        if left != right {
+            points.extend(
+                self.edge(Direction::Left)
+                    .rev()
+                    .filter(|p| p.y != top && p.y != bottom),
+            );
+        }
+        points.into_iter()
+    }
+}
+
+/// The points of a bounding box in reading order, from either end.
+#[derive(Debug, Clone)]
+pub struct BoundingBoxIterator {
+    bbox: BoundingBox,
+    width: u64,
+    front: u64,
+    back: u64,
 }
 
-pub struct BoundingBoxIterator<'b> {
-    bbox: &'b BoundingBox,
-    px: i32,
-    py: i32,
+impl BoundingBoxIterator {
+    fn point(&self, index: u64) -> Point {
+        Point::new(
+            (i64::from(self.bbox.left) + (index % self.width) as i64) as Position,
+            (i64::from(self.bbox.top) + (index / self.width) as i64) as Position,
+        )
+    }
 }
 
-impl<'b> Iterator for BoundingBoxIterator<'b> {
+impl Iterator for BoundingBoxIterator {
     type Item = Point;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.px == self.bbox.width() {
-            self.px = 0;
-            self.py += 1;
+        if self.front == self.back {
+            return None;
         }
+        self.front += 1;
+        Some(self.point(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.back - self.front) as usize;
+        (remaining, Some(remaining))
+    }
 
-        if self.py >= self.bbox.height() {
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.back.min(self.front.saturating_add(n as u64));
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for BoundingBoxIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
             return None;
         }
-
-        let result = Some(Point {
-            x: self.px + self.bbox.left(),
-            y: self.py + self.bbox.top(),
-        });
-        self.px += 1;
-        result
+        self.back -= 1;
+        Some(self.point(self.back))
     }
 }
 
+impl ExactSizeIterator for BoundingBoxIterator {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -421,6 +823,53 @@ mod tests {
         assert_eq!(steps, others);
     }
 
+    #[test]
+    fn turning() {
+        for direction in Direction::all() {
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.turn_left().turn_left(), direction.reverse());
+            assert_eq!(
+                direction.reflect(Mirror::Slash).reflect(Mirror::Slash),
+                direction
+            );
+        }
+
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(Direction::Up.reflect(Mirror::Slash), Direction::Right);
+        assert_eq!(Direction::Left.reflect(Mirror::Slash), Direction::Down);
+        assert_eq!(Direction::Up.reflect(Mirror::Backslash), Direction::Left);
+        assert_eq!(Direction::Right.reflect(Mirror::Backslash), Direction::Down);
+    }
+
+    #[test]
+    fn compass() {
+        let origin = Point::new(0, 0);
+        for direction in Direction::all() {
+            assert_eq!(
+                origin.step_compass(direction.into()),
+                origin.step(direction)
+            );
+        }
+
+        for heading in Compass::all() {
+            assert_eq!(heading.rotate_left().rotate_right(), heading);
+            assert_eq!(heading.to_string().parse::<Compass>().unwrap(), heading);
+            assert_eq!(
+                origin.step_compass(heading.reverse()),
+                Point::new(-heading.offset().0, -heading.offset().1)
+            );
+        }
+
+        assert_eq!(Compass::all().filter(|c| c.is_diagonal()).count(), 4);
+        let mut columns: Vec<_> = Compass::column_major().collect();
+        columns.sort_by_key(|c| (c.offset().1, c.offset().0));
+        assert_eq!(columns, Compass::all().collect::<Vec<_>>());
+        assert_eq!(Compass::North.rotate_right(), Compass::NorthEast);
+        assert_eq!(origin.step_compass(Compass::SouthWest), Point::new(-1, 1));
+        assert!("X".parse::<Compass>().is_err());
+    }
+
     #[test]
     fn bbox() {
         let mut bbox = BoundingBox::empty();
@@ -468,4 +917,94 @@ mod tests {
         assert_eq!(combined.bottom(), 2);
         assert_eq!(combined.height(), 3);
     }
+
+    #[test]
+    fn bbox_algebra() {
+        let a = BoundingBox::new(0, 3, 0, 3);
+        let b = BoundingBox::new(2, 5, -1, 1);
+
+        assert_eq!(a.area(), 16);
+        assert_eq!(a.intersection(&b), Some(BoundingBox::new(2, 3, 0, 1)));
+        assert!(a.overlaps(&b));
+        assert_eq!(a.overlap_area(&b), 4);
+
+        let far = BoundingBox::new(4, 5, 4, 5);
+        assert_eq!(a.intersection(&far), None);
+        assert_eq!(a.overlap_area(&far), 0);
+        assert_eq!(a.difference(&far), vec![a]);
+
+        let pieces = a.difference(&b);
+        assert_eq!(
+            pieces,
+            vec![BoundingBox::new(0, 3, 2, 3), BoundingBox::new(0, 1, 0, 1)]
+        );
+        assert_eq!(pieces.iter().map(|p| p.area()).sum::<u64>(), 12);
+        assert!(a.difference(&a.margin(1)).is_empty());
+
+        assert!(BoundingBox::empty().is_empty());
+        assert_eq!(BoundingBox::empty().area(), 0);
+    }
+
+    #[test]
+    fn bbox_iteration() {
+        let bbox = BoundingBox::new(-1, 1, 2, 3);
+        let points: Vec<_> = bbox.points().collect();
+        assert_eq!(points.len(), 6);
+        assert_eq!(bbox.points().len(), 6);
+        assert_eq!(points[0], Point::new(-1, 2));
+        assert_eq!(points[3], Point::new(-1, 3));
+
+        let mut reversed: Vec<_> = bbox.points().rev().collect();
+        reversed.reverse();
+        assert_eq!(reversed, points);
+
+        let mut both = bbox.points();
+        assert_eq!(both.next(), Some(Point::new(-1, 2)));
+        assert_eq!(both.next_back(), Some(Point::new(1, 3)));
+        assert_eq!(both.len(), 4);
+        assert_eq!(both.nth(3), Some(Point::new(0, 3)));
+        assert_eq!(both.next(), None);
+
+        assert_eq!(
+            bbox.rows().map(|r| r.collect()).collect::<Vec<Vec<_>>>(),
+            vec![points[..3].to_vec(), points[3..].to_vec()]
+        );
+        assert_eq!(
+            bbox.columns().next_back().unwrap().collect::<Vec<_>>(),
+            vec![Point::new(1, 2), Point::new(1, 3)]
+        );
+        assert_eq!(bbox.edge(Direction::Down).len(), 3);
+        assert_eq!(bbox.edge(Direction::Left).len(), 2);
+
+        let square = BoundingBox::new(0, 2, 0, 2);
+        let perimeter: Vec<_> = square.perimeter().collect();
+        assert_eq!(perimeter.len(), 8);
+        assert_eq!(
+            &perimeter[..4],
+            &[
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(2, 0),
+                Point::new(2, 1)
+            ]
+        );
+        assert!(!perimeter.contains(&Point::new(1, 1)));
+        assert_eq!(BoundingBox::new(0, 0, 0, 3).perimeter().count(), 4);
+        assert_eq!(BoundingBox::new(0, 3, 0, 0).perimeter().count(), 4);
+
+        let empty = BoundingBox::empty();
+        assert_eq!(empty.points().len(), 0);
+        assert_eq!(empty.rows().count(), 0);
+        assert_eq!(empty.perimeter().count(), 0);
+        assert_eq!(empty.edge(Direction::Up).count(), 0);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn bbox_parallel() {
+        let bbox = BoundingBox::new(-5, 20, 3, 40);
+        let mut points: Vec<_> = bbox.par_points().collect();
+        points.sort();
+        assert_eq!(points, bbox.points().collect::<Vec<_>>());
+    }
 }
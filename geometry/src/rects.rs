@@ -0,0 +1,434 @@
+//! Sets of possibly overlapping rectangles, measured without visiting
+//! every point they cover.
+
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+
+use crate::intervals::IntervalSet;
+use crate::{BoundingBox, Point, Position};
+
+/// A collection of bounding boxes, which may overlap each other.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RectangleSet {
+    rects: Vec<BoundingBox>,
+}
+
+impl RectangleSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rectangle, returning its index in the set.
+    pub fn insert(&mut self, bbox: BoundingBox) -> usize {
+        self.rects.push(bbox);
+        self.rects.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// Each rectangle, in the order they were inserted.
+    pub fn iter(&self) -> impl Iterator<Item = &BoundingBox> {
+        self.rects.iter()
+    }
+
+    /// The number of rectangles covering a point.
+    pub fn coverage(&self, point: Point) -> usize {
+        self.rects.iter().filter(|r| r.contains(point)).count()
+    }
+
+    /// Sweep down the plane, giving each band of rows in which the
+    /// covered columns don't change, along with those columns. Rows
+    /// covered by no rectangle are skipped.
+    pub fn sweep(&self) -> Vec<(RangeInclusive<Position>, IntervalSet)> {
+        let mut bands: Vec<(RangeInclusive<Position>, IntervalSet)> = Vec::new();
+        self.sweep_rows(1, |start, end, coverage| {
+            if coverage.width() == 0 {
+                return;
+            }
+            let columns = coverage.columns();
+
+            let (start, end) = (start as Position, (end - 1) as Position);
+            match bands.last_mut() {
+                Some((rows, previous)) if *previous == columns && *rows.end() + 1 == start => {
+                    *rows = *rows.start()..=end;
+                }
+                _ => bands.push((start..=end, columns)),
+            }
+        });
+        bands
+    }
+
+    /// The number of points covered by at least one rectangle.
+    pub fn area(&self) -> u64 {
+        self.area_covered_at_least(1)
+    }
+
+    /// The number of points covered by at least `n` rectangles.
+    pub fn area_covered_at_least(&self, n: usize) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+
+        let mut area = 0;
+        self.sweep_rows(n, |start, end, coverage| {
+            area += coverage.width() * (end - start) as u64;
+        });
+        area
+    }
+
+    /// Visit each band of rows `start..end` between consecutive top and
+    /// bottom edges, with the columns covered at least `depth` times.
+    ///
+    /// Rectangles are added to the coverage at their top edge and removed
+    /// just after their bottom edge, so each edge is handled once.
+    fn sweep_rows<F>(&self, depth: usize, mut f: F)
+    where
+        F: FnMut(i64, i64, &Coverage),
+    {
+        let rects: Vec<&BoundingBox> = self.rects.iter().filter(|r| !r.is_empty()).collect();
+
+        // Edges are half open, so that a rectangle ends just after
+        // `right` and `bottom`.
+        let mut xs: Vec<i64> = rects
+            .iter()
+            .flat_map(|r| vec![i64::from(r.left()), i64::from(r.right()) + 1])
+            .collect();
+        xs.sort();
+        xs.dedup();
+
+        let column = |x: i64| xs.binary_search(&x).expect("Every edge is listed");
+        let mut events: Vec<(i64, isize, usize, usize)> = rects
+            .iter()
+            .flat_map(|r| {
+                let (left, right) = (column(r.left().into()), column(i64::from(r.right()) + 1));
+                vec![
+                    (i64::from(r.top()), 1, left, right),
+                    (i64::from(r.bottom()) + 1, -1, left, right),
+                ]
+            })
+            .collect();
+        events.sort();
+
+        let mut coverage = Coverage::new(&xs, depth);
+        for (i, (y, change, left, right)) in events.iter().enumerate() {
+            coverage.update(*left, *right, *change);
+            match events.get(i + 1) {
+                Some((next, ..)) if next > y => f(*y, *next, &coverage),
+                _ => {}
+            }
+        }
+    }
+
+    /// The number of points covered by more than one rectangle.
+    pub fn overlap_area(&self) -> u64 {
+        self.area_covered_at_least(2)
+    }
+
+    /// The indices of rectangles which overlap no other rectangle,
+    /// in the order they were inserted.
+    pub fn isolated(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.rects.len()).collect();
+        order.sort_by_key(|&i| self.rects[i].left());
+
+        let mut overlapped = vec![false; self.rects.len()];
+        for (position, &i) in order.iter().enumerate() {
+            let rect = &self.rects[i];
+            for &j in &order[position + 1..] {
+                if self.rects[j].left() > rect.right() {
+                    break;
+                }
+                if rect.overlaps(&self.rects[j]) {
+                    overlapped[i] = true;
+                    overlapped[j] = true;
+                }
+            }
+        }
+
+        (0..self.rects.len())
+            .filter(|&i| !overlapped[i] && !self.rects[i].is_empty())
+            .collect()
+    }
+
+    /// The points covered by rectangles, as disjoint boxes.
+    pub fn disjoint(&self) -> Vec<BoundingBox> {
+        let mut pieces: Vec<BoundingBox> = Vec::new();
+        for rect in &self.rects {
+            let mut remainder = vec![*rect];
+            for piece in &pieces {
+                remainder = remainder.iter().flat_map(|r| r.difference(piece)).collect();
+            }
+            pieces.extend(remainder);
+        }
+        pieces
+    }
+
+    /// The smallest box containing every rectangle.
+    pub fn bbox(&self) -> BoundingBox {
+        self.rects
+            .iter()
+            .filter(|r| !r.is_empty())
+            .fold(BoundingBox::empty(), |bbox, r| bbox.union(r))
+    }
+
+    /// The largest number of rectangles covering any single point.
+    pub fn max_coverage(&self) -> usize {
+        let mut n = 0;
+        self.sweep_rows(1, |_, _, coverage| {
+            n = n.max(coverage.deepest());
+        });
+        n
+    }
+}
+
+/// How many times each column is covered, as intervals of columns are
+/// added and removed.
+///
+/// A segment tree over the columns between consecutive edges. Each node
+/// counts the intervals which cover its whole span without covering its
+/// parent's, the width of its span covered at least `1..=depth` times,
+/// and the most times any column in its span is covered.
+struct Coverage<'e> {
+    edges: &'e [i64],
+    depth: usize,
+    count: Vec<isize>,
+    covered: Vec<u64>,
+    deepest: Vec<isize>,
+}
+
+impl<'e> Coverage<'e> {
+    fn new(edges: &'e [i64], depth: usize) -> Self {
+        let nodes = 4 * edges.len().max(1);
+        Self {
+            edges,
+            depth,
+            count: vec![0; nodes],
+            covered: vec![0; nodes * depth],
+            deepest: vec![0; nodes],
+        }
+    }
+
+    /// The most times any column is covered.
+    fn deepest(&self) -> usize {
+        if self.edges.len() < 2 {
+            return 0;
+        }
+        self.deepest[1] as usize
+    }
+
+    /// The number of columns covered at least `depth` times.
+    fn width(&self) -> u64 {
+        if self.edges.len() < 2 {
+            return 0;
+        }
+        self.covered(1, 0, self.edges.len() - 1, self.depth)
+    }
+
+    /// The columns covered at least `depth` times.
+    fn columns(&self) -> IntervalSet {
+        let mut columns = Vec::new();
+        if self.edges.len() >= 2 {
+            self.collect(1, 0, self.edges.len() - 1, self.depth, &mut columns);
+        }
+        columns.into_iter().collect()
+    }
+
+    /// Add `change` to the coverage of the columns from edge `left` up
+    /// to, but not including, edge `right`.
+    fn update(&mut self, left: usize, right: usize, change: isize) {
+        self.update_node(1, 0, self.edges.len() - 1, left, right, change);
+    }
+
+    fn covered(&self, node: usize, lo: usize, hi: usize, times: usize) -> u64 {
+        if times == 0 {
+            (self.edges[hi] - self.edges[lo]) as u64
+        } else {
+            self.covered[node * self.depth + times - 1]
+        }
+    }
+
+    fn update_node(
+        &mut self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        left: usize,
+        right: usize,
+        change: isize,
+    ) {
+        if right <= lo || hi <= left {
+            return;
+        }
+        if left <= lo && hi <= right {
+            self.count[node] += change;
+        } else {
+            let mid = (lo + hi) / 2;
+            self.update_node(2 * node, lo, mid, left, right, change);
+            self.update_node(2 * node + 1, mid, hi, left, right, change);
+        }
+
+        let mid = (lo + hi) / 2;
+        self.deepest[node] = self.count[node]
+            + if hi - lo == 1 {
+                0
+            } else {
+                self.deepest[2 * node].max(self.deepest[2 * node + 1])
+            };
+
+        let count = self.count[node] as usize;
+        let width = (self.edges[hi] - self.edges[lo]) as u64;
+        for times in 1..=self.depth {
+            self.covered[node * self.depth + times - 1] = if count >= times {
+                width
+            } else if hi - lo == 1 {
+                0
+            } else {
+                self.covered(2 * node, lo, mid, times - count)
+                    + self.covered(2 * node + 1, mid, hi, times - count)
+            };
+        }
+    }
+
+    fn collect(
+        &self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        times: usize,
+        columns: &mut Vec<RangeInclusive<Position>>,
+    ) {
+        let count = self.count[node] as usize;
+        if count >= times {
+            columns.push(self.edges[lo] as Position..=(self.edges[hi] - 1) as Position);
+        } else if self.covered(node, lo, hi, times) > 0 {
+            let mid = (lo + hi) / 2;
+            self.collect(2 * node, lo, mid, times - count, columns);
+            self.collect(2 * node + 1, mid, hi, times - count, columns);
+        }
+    }
+}
+
+impl FromIterator<BoundingBox> for RectangleSet {
+    fn from_iter<I: IntoIterator<Item = BoundingBox>>(iter: I) -> Self {
+        Self {
+            rects: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<BoundingBox> for RectangleSet {
+    fn extend<I: IntoIterator<Item = BoundingBox>>(&mut self, iter: I) {
+        self.rects.extend(iter)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn claims() -> RectangleSet {
+        vec![
+            BoundingBox::new(1, 4, 3, 6),
+            BoundingBox::new(3, 6, 1, 4),
+            BoundingBox::new(5, 6, 5, 6),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// Count coverage point by point, to check the sweep against.
+    fn rasterized(set: &RectangleSet, n: usize) -> u64 {
+        set.bbox()
+            .points()
+            .filter(|p| set.coverage(*p) >= n)
+            .count() as u64
+    }
+
+    #[test]
+    fn areas() {
+        let set = claims();
+        assert_eq!(set.overlap_area(), 4);
+        assert_eq!(set.area(), 16 + 16 - 4 + 4);
+        assert_eq!(set.isolated(), vec![2]);
+        assert_eq!(set.max_coverage(), 2);
+
+        let disjoint = set.disjoint();
+        assert_eq!(disjoint.iter().map(|b| b.area()).sum::<u64>(), set.area());
+        for (i, a) in disjoint.iter().enumerate() {
+            for b in &disjoint[i + 1..] {
+                assert!(!a.overlaps(b));
+            }
+        }
+    }
+
+    #[test]
+    fn against_rasterized() {
+        let mut set = RectangleSet::new();
+        for i in 0..12 {
+            let (x, y) = ((i * 7) % 11 - 5, (i * 5) % 13 - 6);
+            set.insert(BoundingBox::new(x, x + i % 4 + 1, y, y + i % 5));
+        }
+        set.insert(BoundingBox::empty());
+
+        for n in 1..5 {
+            assert_eq!(set.area_covered_at_least(n), rasterized(&set, n));
+        }
+        assert_eq!(RectangleSet::new().area(), 0);
+        assert_eq!(RectangleSet::new().max_coverage(), 0);
+    }
+
+    #[test]
+    fn many_against_rasterized() {
+        let mut set = RectangleSet::new();
+        let mut seed: i64 = 17;
+        let mut next = |n: i64| {
+            seed = (seed * 1_103_515_245 + 12345) % (1 << 31);
+            (seed % n) as Position
+        };
+        for _ in 0..300 {
+            let (x, y) = (next(90), next(90));
+            set.insert(BoundingBox::new(x, x + next(12), y, y + next(12)));
+        }
+
+        for n in 1..6 {
+            assert_eq!(set.area_covered_at_least(n), rasterized(&set, n));
+        }
+        let deepest = set.bbox().points().map(|p| set.coverage(p)).max();
+        assert_eq!(Some(set.max_coverage()), deepest);
+        let swept: u64 = set
+            .sweep()
+            .iter()
+            .map(|(rows, columns)| (rows.end() - rows.start() + 1) as u64 * columns.len())
+            .sum();
+        assert_eq!(swept, set.area());
+    }
+
+    #[test]
+    fn sweep() {
+        let bands = claims().sweep();
+        assert_eq!(
+            bands,
+            vec![
+                (1..=2, IntervalSet::from(3..=6)),
+                (3..=6, vec![1..=4, 3..=6].into_iter().collect()),
+            ]
+        );
+        assert_eq!(bands[1].1.len(), 6);
+
+        let apart: RectangleSet = vec![BoundingBox::new(0, 1, 0, 0), BoundingBox::new(0, 0, 3, 4)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            apart.sweep(),
+            vec![
+                (0..=0, IntervalSet::from(0..=1)),
+                (3..=4, IntervalSet::from(0..=0)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Flood fill and connected regions of points.
+//!
+//! Regions are connected through the four adjacent points, and are
+//! unbounded when they reach the edge of the area being searched,
+//! since they might continue beyond it.
+
+use crate::grid::Grid;
+use crate::{BoundingBox, Point};
+
+/// A connected set of points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    points: Vec<Point>,
+    bbox: BoundingBox,
+    unbounded: bool,
+}
+
+impl Region {
+    fn new(mut points: Vec<Point>, area: BoundingBox) -> Self {
+        points.sort();
+        let bbox = BoundingBox::from_points(points.iter().cloned());
+        let unbounded = points.iter().any(|p| area.is_edge(*p));
+        Self {
+            points,
+            bbox,
+            unbounded,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Every point in the region, in reading order.
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.points.binary_search(&point).is_ok()
+    }
+
+    /// The smallest box containing the region.
+    pub fn bbox(&self) -> BoundingBox {
+        self.bbox
+    }
+
+    /// Whether the region touches the edge of the searched area.
+    pub fn is_unbounded(&self) -> bool {
+        self.unbounded
+    }
+}
+
+/// Visit every point reachable from the seeds inside `area`, where
+/// `connected` decides if a step from one point to the next is allowed.
+fn fill<F>(area: BoundingBox, seeds: Vec<Point>, mut connected: F, seen: &mut Grid<bool>) -> Region
+where
+    F: FnMut(Point, Point) -> bool,
+{
+    let mut points = Vec::new();
+    let mut stack = Vec::new();
+    for seed in seeds {
+        if !seen[seed] {
+            seen[seed] = true;
+            stack.push(seed);
+        }
+    }
+
+    while let Some(point) = stack.pop() {
+        points.push(point);
+        for next in point.adjacent() {
+            if area.contains(next) && !seen[next] && connected(point, next) {
+                seen[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+
+    Region::new(points, area)
+}
+
+/// Fill outwards from the seeds through passable points inside `area`.
+/// Seeds which are outside the area or impassable are ignored.
+pub fn flood_fill<I, F>(area: BoundingBox, seeds: I, mut passable: F) -> Region
+where
+    I: IntoIterator<Item = Point>,
+    F: FnMut(Point) -> bool,
+{
+    let mut seen = Grid::new(area, false);
+    let seeds = seeds
+        .into_iter()
+        .filter(|s| area.contains(*s) && passable(*s))
+        .collect();
+    fill(area, seeds, |_, next| passable(next), &mut seen)
+}
+
+/// The connected regions of a grid, each with a label given by the
+/// order in which it was found, scanning the grid in reading order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Components {
+    labels: Grid<Option<usize>>,
+    regions: Vec<Region>,
+}
+
+impl Components {
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// The label of the region containing `point`, if any.
+    pub fn label(&self, point: Point) -> Option<usize> {
+        self.labels.get(point).and_then(|l| *l)
+    }
+
+    pub fn get(&self, label: usize) -> Option<&Region> {
+        self.regions.get(label)
+    }
+
+    pub fn region_at(&self, point: Point) -> Option<&Region> {
+        self.label(point).and_then(|l| self.get(l))
+    }
+
+    /// Each region, in label order.
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter()
+    }
+
+    /// The regions which don't touch the edge of the grid.
+    pub fn bounded(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter().filter(|r| !r.is_unbounded())
+    }
+
+    /// The region with the most points, preferring the lowest label.
+    pub fn largest(&self) -> Option<&Region> {
+        self.regions.iter().rev().max_by_key(|r| r.size())
+    }
+}
+
+/// Label the regions of passable points in a grid.
+pub fn components<T, F>(grid: &Grid<T>, passable: F) -> Components
+where
+    F: FnMut(&T) -> bool,
+{
+    components_by(grid, passable, |_, _| true)
+}
+
+/// Label the regions of passable points in a grid, where adjacent
+/// points are only connected if `same` is true of their values.
+/// `same` should be symmetric, like an equality test.
+pub fn components_by<T, F, S>(grid: &Grid<T>, mut passable: F, mut same: S) -> Components
+where
+    F: FnMut(&T) -> bool,
+    S: FnMut(&T, &T) -> bool,
+{
+    let area = grid.bbox();
+    let mut seen = grid.map(|_, v| !passable(v));
+    let mut labels = Grid::new(area, None);
+    let mut regions = Vec::new();
+
+    for (point, _) in grid.iter() {
+        if seen[point] {
+            continue;
+        }
+
+        let region = fill(
+            area,
+            vec![point],
+            |from, to| same(&grid[from], &grid[to]),
+            &mut seen,
+        );
+        for p in region.points() {
+            labels[*p] = Some(regions.len());
+        }
+        regions.push(region);
+    }
+
+    Components { labels, regions }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ascii::render;
+
+    /// Letters are regions, `#` is impassable.
+    fn map() -> Grid<char> {
+        let text = "aa#bb\na##bb\n#cc#b\n#c#dd\n";
+        let lines: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+        Grid::from_fn(BoundingBox::new(0, 4, 0, 3), |p| {
+            lines[p.y as usize][p.x as usize]
+        })
+    }
+
+    #[test]
+    fn fill_from_seeds() {
+        let grid = map();
+        let region = flood_fill(grid.bbox(), vec![Point::new(1, 2)], |p| grid[p] == 'c');
+        assert_eq!(
+            region.points(),
+            &[Point::new(1, 2), Point::new(2, 2), Point::new(1, 3)]
+        );
+        assert_eq!(region.bbox(), BoundingBox::new(1, 2, 2, 3));
+        assert!(region.is_unbounded());
+
+        let open = flood_fill(grid.bbox(), vec![Point::new(0, 0)], |p| grid[p] != '#');
+        assert_eq!(open.size(), 3);
+
+        let none = flood_fill(grid.bbox(), vec![Point::new(2, 0)], |p| grid[p] != '#');
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn labelling() {
+        let grid = map();
+        let labelled = components_by(&grid, |c| *c != '#', |a, b| a == b);
+        assert_eq!(labelled.len(), 4);
+
+        let labels = labelled.labels.map(|_, l| match l {
+            Some(l) => (b'0' + *l as u8) as char,
+            None => '#',
+        });
+        assert_eq!(render(&labels, |_, c| *c), "00#11\n0##11\n#22#1\n#2#33\n");
+
+        assert_eq!(labelled.largest().map(|r| r.size()), Some(5));
+        assert_eq!(labelled.region_at(Point::new(4, 3)).unwrap().size(), 2);
+        assert_eq!(labelled.label(Point::new(2, 0)), None);
+
+        // Everything touches the edge of such a small grid.
+        assert_eq!(labelled.bounded().count(), 0);
+
+        // Without comparing values, b and d join up.
+        assert_eq!(components(&grid, |c| *c != '#').len(), 3);
+    }
+
+    #[test]
+    fn bounded_regions() {
+        let grid = Grid::from_fn(BoundingBox::new(0, 4, 0, 4), |p| {
+            p.x == 0 || p.x == 4 || p.y == 0 || p.y == 4 || p == Point::new(2, 2)
+        });
+        let labelled = components(&grid, |wall| !*wall);
+        assert_eq!(labelled.len(), 1);
+        assert_eq!(labelled.bounded().map(|r| r.size()).sum::<usize>(), 8);
+    }
+}
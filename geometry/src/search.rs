@@ -0,0 +1,411 @@
+//! Shortest path searches over any state with a successor function.
+//!
+//! States must be ordered so that ties are broken deterministically:
+//! among equally good states the smallest is expanded first, and each
+//! state remembers the smallest predecessor on a shortest path to it.
+//! For `Point`, that is reading order.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use std::ops::Add;
+
+/// The cost of a step, which must never be negative.
+pub trait Cost: Copy + Ord + Default + Add<Output = Self> {}
+
+impl<C> Cost for C where C: Copy + Ord + Default + Add<Output = C> {}
+
+/// A path from an origin to a destination, including both ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<S, C> {
+    states: Vec<S>,
+    cost: C,
+}
+
+impl<S, C: Cost> Path<S, C> {
+    pub fn cost(&self) -> C {
+        self.cost
+    }
+
+    pub fn origin(&self) -> &S {
+        &self.states[0]
+    }
+
+    pub fn destination(&self) -> &S {
+        &self.states[self.states.len() - 1]
+    }
+
+    /// The first state after the origin, if the path goes anywhere.
+    pub fn first_step(&self) -> Option<&S> {
+        self.states.get(1)
+    }
+
+    /// The number of steps taken, which is one less than the states visited.
+    pub fn steps(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+}
+
+/// Every state reached by a search, with its cost and predecessor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explored<S: Ord, C> {
+    visited: BTreeMap<S, (C, Option<S>)>,
+}
+
+impl<S, C> Explored<S, C>
+where
+    S: Ord + Clone,
+    C: Cost,
+{
+    fn new() -> Self {
+        Self {
+            visited: BTreeMap::new(),
+        }
+    }
+
+    /// Record a way of reaching `state`, returning whether it was an
+    /// improvement on what was already known.
+    fn relax(&mut self, state: S, cost: C, parent: Option<S>) -> bool {
+        match self.visited.get_mut(&state) {
+            Some(known) => {
+                if (cost, &parent) < (known.0, &known.1) {
+                    *known = (cost, parent);
+                    true
+                } else {
+                    false
+                }
+            }
+            None => {
+                self.visited.insert(state, (cost, parent));
+                true
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.visited.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.visited.is_empty()
+    }
+
+    pub fn contains(&self, state: &S) -> bool {
+        self.visited.contains_key(state)
+    }
+
+    /// The cost of the cheapest path found to `state`.
+    pub fn cost(&self, state: &S) -> Option<C> {
+        self.visited.get(state).map(|(cost, _)| *cost)
+    }
+
+    /// Each state reached and its cost, in state order.
+    pub fn iter(&self) -> impl Iterator<Item = (&S, C)> {
+        self.visited.iter().map(|(state, (cost, _))| (state, *cost))
+    }
+
+    /// Reconstruct the cheapest path found to `state`.
+    pub fn path(&self, state: &S) -> Option<Path<S, C>> {
+        let (cost, mut parent) = self.visited.get(state).cloned()?;
+        let mut states = vec![state.clone()];
+        while let Some(p) = parent {
+            parent = self.visited[&p].1.clone();
+            states.push(p);
+        }
+        states.reverse();
+        Some(Path { states, cost })
+    }
+
+    /// The cheapest path to any state matching `target`, preferring
+    /// the smallest state when several are equally cheap.
+    pub fn nearest<F>(&self, mut target: F) -> Option<Path<S, C>>
+    where
+        F: FnMut(&S) -> bool,
+    {
+        self.visited
+            .iter()
+            .filter(|(state, _)| target(state))
+            .min_by(|a, b| ((a.1).0, a.0).cmp(&((b.1).0, b.0)))
+            .and_then(|(state, _)| self.path(state))
+    }
+}
+
+/// Breadth first search from every origin, until a level of states
+/// contains a target, or every reachable state has been found.
+fn breadth_first<S, I, F, G>(
+    origins: I,
+    mut successors: F,
+    mut target: G,
+) -> (Explored<S, usize>, Option<S>)
+where
+    S: Ord + Clone,
+    I: IntoIterator<Item = S>,
+    F: FnMut(&S) -> Vec<S>,
+    G: FnMut(&S) -> bool,
+{
+    let mut explored = Explored::new();
+    let mut level: BTreeSet<S> = origins.into_iter().collect();
+    for origin in &level {
+        explored.relax(origin.clone(), 0, None);
+    }
+
+    let mut distance = 0;
+    while !level.is_empty() {
+        if let Some(found) = level.iter().find(|s| target(s)) {
+            return (explored, Some(found.clone()));
+        }
+
+        distance += 1;
+        let mut next = BTreeSet::new();
+        for state in &level {
+            for successor in successors(state) {
+                if !explored.contains(&successor) {
+                    explored.relax(successor.clone(), distance, Some(state.clone()));
+                    next.insert(successor);
+                }
+            }
+        }
+        level = next;
+    }
+
+    (explored, None)
+}
+
+/// Best first search, expanding states in order of cost plus heuristic.
+fn best_first<S, C, I, F, H, G>(
+    origins: I,
+    mut successors: F,
+    mut heuristic: H,
+    mut target: G,
+) -> (Explored<S, C>, Option<S>)
+where
+    S: Ord + Clone,
+    C: Cost,
+    I: IntoIterator<Item = S>,
+    F: FnMut(&S) -> Vec<(S, C)>,
+    H: FnMut(&S) -> C,
+    G: FnMut(&S) -> bool,
+{
+    let mut explored = Explored::new();
+    let mut closed = BTreeSet::new();
+    let mut queue = BinaryHeap::new();
+
+    for origin in origins {
+        if explored.relax(origin.clone(), C::default(), None) {
+            queue.push(Reverse((heuristic(&origin), origin, C::default())));
+        }
+    }
+
+    while let Some(Reverse((_, state, cost))) = queue.pop() {
+        if closed.contains(&state) || explored.cost(&state) != Some(cost) {
+            continue;
+        }
+
+        if target(&state) {
+            return (explored, Some(state));
+        }
+
+        for (successor, step) in successors(&state) {
+            let total = cost + step;
+
+            // Closed states are only reopened if they are now cheaper to
+            // reach, which can happen when the heuristic is inconsistent.
+            if closed.contains(&successor) && explored.cost(&successor) <= Some(total) {
+                continue;
+            }
+            if explored.relax(successor.clone(), total, Some(state.clone())) {
+                closed.remove(&successor);
+                queue.push(Reverse((total + heuristic(&successor), successor, total)));
+            }
+        }
+        closed.insert(state);
+    }
+
+    (explored, None)
+}
+
+/// Find the distance to every state reachable from the origins, where
+/// each step has the same cost.
+pub fn bfs<S, I, F>(origins: I, successors: F) -> Explored<S, usize>
+where
+    S: Ord + Clone,
+    I: IntoIterator<Item = S>,
+    F: FnMut(&S) -> Vec<S>,
+{
+    breadth_first(origins, successors, |_| false).0
+}
+
+/// Find the shortest path to the nearest target, where each step has
+/// the same cost. Equally near targets are chosen in state order.
+pub fn bfs_nearest<S, I, F, G>(origins: I, successors: F, target: G) -> Option<Path<S, usize>>
+where
+    S: Ord + Clone,
+    I: IntoIterator<Item = S>,
+    F: FnMut(&S) -> Vec<S>,
+    G: FnMut(&S) -> bool,
+{
+    let (explored, found) = breadth_first(origins, successors, target);
+    explored.path(&found?)
+}
+
+/// Find the cheapest path to every state reachable from the origins.
+pub fn dijkstra<S, C, I, F>(origins: I, successors: F) -> Explored<S, C>
+where
+    S: Ord + Clone,
+    C: Cost,
+    I: IntoIterator<Item = S>,
+    F: FnMut(&S) -> Vec<(S, C)>,
+{
+    best_first(origins, successors, |_| C::default(), |_| false).0
+}
+
+/// Find the cheapest path to the nearest target. Equally cheap targets
+/// are chosen in state order, as long as every step has a positive cost.
+pub fn dijkstra_nearest<S, C, I, F, G>(origins: I, successors: F, target: G) -> Option<Path<S, C>>
+where
+    S: Ord + Clone,
+    C: Cost,
+    I: IntoIterator<Item = S>,
+    F: FnMut(&S) -> Vec<(S, C)>,
+    G: FnMut(&S) -> bool,
+{
+    let (explored, found) = best_first(origins, successors, |_| C::default(), target);
+    explored.path(&found?)
+}
+
+/// Find the cheapest path to a target, guided by a heuristic which must
+/// never overestimate the remaining cost. The heuristic need not be
+/// consistent, as states found more cheaply after expansion are reopened.
+pub fn astar<S, C, F, H, G>(origin: S, successors: F, heuristic: H, target: G) -> Option<Path<S, C>>
+where
+    S: Ord + Clone,
+    C: Cost,
+    F: FnMut(&S) -> Vec<(S, C)>,
+    H: FnMut(&S) -> C,
+    G: FnMut(&S) -> bool,
+{
+    let (explored, found) = best_first(Some(origin), successors, heuristic, target);
+    explored.path(&found?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{BoundingBox, Point};
+
+    /// Open points in a small maze, with a wall down the middle.
+    ///
+    /// ```text
+    /// .....
+    /// .#.#.
+    /// .#...
+    /// ```
+    fn maze(point: &Point) -> Vec<Point> {
+        let bbox = BoundingBox::new(0, 4, 0, 2);
+        let walls = [Point::new(1, 1), Point::new(1, 2), Point::new(3, 1)];
+        point
+            .adjacent()
+            .filter(|p| bbox.contains(*p) && !walls.contains(p))
+            .collect()
+    }
+
+    #[test]
+    fn distances() {
+        let explored = bfs(vec![Point::new(0, 2)], maze);
+        assert_eq!(explored.len(), 12);
+        assert_eq!(explored.cost(&Point::new(4, 2)), Some(8));
+        assert_eq!(explored.cost(&Point::new(1, 1)), None);
+
+        let path = explored.path(&Point::new(2, 2)).unwrap();
+        assert_eq!(path.steps(), 6);
+        assert_eq!(path.first_step(), Some(&Point::new(0, 1)));
+    }
+
+    #[test]
+    fn nearest_in_reading_order() {
+        let targets = [Point::new(4, 0), Point::new(2, 2), Point::new(4, 2)];
+        let path = bfs_nearest(vec![Point::new(2, 0)], maze, |p| targets.contains(p)).unwrap();
+        assert_eq!(path.destination(), &Point::new(4, 0));
+        assert_eq!(path.steps(), 2);
+
+        // Going around either side of the wall takes four steps, so the
+        // path goes through the points which come first in reading order.
+        let path = bfs_nearest(vec![Point::new(2, 1)], maze, |p| *p == Point::new(4, 1)).unwrap();
+        assert_eq!(
+            path.states(),
+            &[
+                Point::new(2, 1),
+                Point::new(2, 0),
+                Point::new(3, 0),
+                Point::new(4, 0),
+                Point::new(4, 1)
+            ]
+        );
+
+        assert_eq!(bfs_nearest(vec![Point::new(0, 0)], maze, |_| false), None);
+    }
+
+    /// Moving right is cheap, any other direction is expensive.
+    fn weighted(point: &Point) -> Vec<(Point, u32)> {
+        maze(point)
+            .into_iter()
+            .map(|p| (p, if p.x > point.x { 1 } else { 5 }))
+            .collect()
+    }
+
+    #[test]
+    fn weighted_searches() {
+        let explored = dijkstra(vec![Point::new(0, 0)], weighted);
+        assert_eq!(explored.cost(&Point::new(4, 0)), Some(4));
+        assert_eq!(explored.cost(&Point::new(2, 2)), Some(12));
+        assert_eq!(explored.cost(&Point::new(0, 0)), Some(0));
+
+        let path =
+            dijkstra_nearest(vec![Point::new(0, 0)], weighted, |p| p.y == 2 && p.x > 0).unwrap();
+        assert_eq!(path.cost(), 12);
+        assert_eq!(path.destination(), &Point::new(2, 2));
+
+        let goal = Point::new(4, 2);
+        let path = astar(
+            Point::new(0, 0),
+            weighted,
+            |p| p.manhattan_distance(goal) as u32,
+            |p| *p == goal,
+        )
+        .unwrap();
+        assert_eq!(path.cost(), explored.cost(&goal).unwrap());
+        assert_eq!(path.steps(), 6);
+    }
+
+    #[test]
+    fn astar_inconsistent_heuristic() {
+        // 0 -> 1 -> 2 is cheaper than 0 -> 2, but the heuristic overstates
+        // the cost from 1, so 2 is expanded before its cheaper path is found.
+        let edges = |state: &u8| -> Vec<(u8, u32)> {
+            match state {
+                0 => vec![(1, 1), (2, 3)],
+                1 => vec![(2, 1)],
+                2 => vec![(3, 3)],
+                _ => Vec::new(),
+            }
+        };
+        let heuristic = |state: &u8| if *state == 1 { 4 } else { 0 };
+
+        let path = astar(0, edges, heuristic, |state| *state == 3).unwrap();
+        assert_eq!(path.cost(), 5);
+        assert_eq!(path.states(), &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn nearest_explored() {
+        let explored = bfs(vec![Point::new(0, 0), Point::new(4, 0)], maze);
+        let path = explored.nearest(|p| p.y == 2).unwrap();
+        assert_eq!(path.destination(), &Point::new(0, 2));
+        assert_eq!(path.cost(), 2);
+        assert_eq!(explored.iter().map(|(_, c)| c).max(), Some(4));
+    }
+}
@@ -0,0 +1,155 @@
+//! Compact serde representations, enabled by the `serde` feature.
+//!
+//! Points are stored as arrays of coordinates, bounding boxes as their
+//! top left and bottom right corners, and grids as a bounding box with
+//! the values in reading order, so `Point::new(1, 2)` becomes `[1,2]`
+//! in JSON.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::grid::Grid;
+use crate::space::{Point3D, Point4D};
+use crate::{BoundingBox, Point, Position};
+
+impl Serialize for Point {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.x, self.y).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Point {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (x, y) = Deserialize::deserialize(deserializer)?;
+        Ok(Point::new(x, y))
+    }
+}
+
+impl Serialize for Point3D {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.x, self.y, self.z).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Point3D {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (x, y, z) = Deserialize::deserialize(deserializer)?;
+        Ok(Point3D::new(x, y, z))
+    }
+}
+
+impl Serialize for Point4D {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.x, self.y, self.z, self.w).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Point4D {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (x, y, z, w) = Deserialize::deserialize(deserializer)?;
+        Ok(Point4D::new(x, y, z, w))
+    }
+}
+
+impl Serialize for BoundingBox {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ((self.left(), self.top()), (self.right(), self.bottom())).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BoundingBox {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ((left, top), (right, bottom)): ((Position, Position), (Position, Position)) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(BoundingBox::new(left, right, top, bottom))
+    }
+}
+
+#[derive(Serialize)]
+struct GridRef<'g, T> {
+    bbox: BoundingBox,
+    cells: Vec<&'g T>,
+}
+
+#[derive(Deserialize)]
+struct GridData<T> {
+    bbox: BoundingBox,
+    cells: Vec<T>,
+}
+
+impl<T: Serialize> Serialize for Grid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GridRef {
+            bbox: self.bbox(),
+            cells: self.values().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Grid<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data: GridData<T> = Deserialize::deserialize(deserializer)?;
+        if data.cells.len() as u64 != data.bbox.area() {
+            return Err(D::Error::custom(format!(
+                "expected {} cells for a {}x{} grid, found {}",
+                data.bbox.area(),
+                data.bbox.width(),
+                data.bbox.height(),
+                data.cells.len()
+            )));
+        }
+        Ok(Grid::from_cells(data.bbox, data.cells))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{Compass, Direction};
+
+    #[test]
+    fn points() {
+        let point = Point::new(1, -2);
+        assert_eq!(serde_json::to_string(&point).unwrap(), "[1,-2]");
+        assert_eq!(serde_json::from_str::<Point>("[1,-2]").unwrap(), point);
+
+        let point = Point4D::new(1, 2, 3, 4);
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(json, "[1,2,3,4]");
+        assert_eq!(serde_json::from_str::<Point4D>(&json).unwrap(), point);
+
+        assert!(serde_json::from_str::<Point3D>("[1,2]").is_err());
+    }
+
+    #[test]
+    fn bbox() {
+        let bbox = BoundingBox::new(-1, 3, 0, 2);
+        let json = serde_json::to_string(&bbox).unwrap();
+        assert_eq!(json, "[[-1,0],[3,2]]");
+        assert_eq!(serde_json::from_str::<BoundingBox>(&json).unwrap(), bbox);
+    }
+
+    #[test]
+    fn directions() {
+        let json = serde_json::to_string(&(Direction::Up, Compass::SouthWest)).unwrap();
+        assert_eq!(json, r#"["Up","SouthWest"]"#);
+        assert_eq!(
+            serde_json::from_str::<(Direction, Compass)>(&json).unwrap(),
+            (Direction::Up, Compass::SouthWest)
+        );
+    }
+
+    #[test]
+    fn grid() {
+        let grid = Grid::from_fn(BoundingBox::new(0, 1, 5, 6), |p| p.x + p.y);
+        let json = serde_json::to_string(&grid).unwrap();
+        assert_eq!(json, r#"{"bbox":[[0,5],[1,6]],"cells":[5,6,6,7]}"#);
+        assert_eq!(serde_json::from_str::<Grid<i32>>(&json).unwrap(), grid);
+
+        assert!(
+            serde_json::from_str::<Grid<i32>>(r#"{"bbox":[[0,5],[1,6]],"cells":[5]}"#).is_err()
+        );
+    }
+}
@@ -0,0 +1,425 @@
+//! Points in more than two dimensions, and the operations shared by
+//! points of any dimension.
+
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{ParsePointError, Point, Position};
+
+/// A point with a fixed number of integer coordinates.
+pub trait Coordinates: Copy + Eq + Ord + Hash + fmt::Debug {
+    const DIMENSIONS: usize;
+
+    /// The coordinate along an axis, which must be less than `DIMENSIONS`.
+    fn coordinate(&self, axis: usize) -> Position;
+
+    /// A point with each coordinate given by its axis.
+    fn from_fn<F: FnMut(usize) -> Position>(f: F) -> Self;
+
+    fn origin() -> Self {
+        Self::from_fn(|_| 0)
+    }
+
+    /// Move along a single axis.
+    fn offset(self, axis: usize, delta: Position) -> Self {
+        Self::from_fn(|a| {
+            if a == axis {
+                self.coordinate(a) + delta
+            } else {
+                self.coordinate(a)
+            }
+        })
+    }
+
+    fn manhattan_distance(self, other: Self) -> Position {
+        (0..Self::DIMENSIONS)
+            .map(|a| (self.coordinate(a) - other.coordinate(a)).abs())
+            .sum()
+    }
+
+    /// The points one step away along each axis, in both directions.
+    fn neighbours(self) -> Vec<Self> {
+        let mut points = Vec::with_capacity(Self::DIMENSIONS * 2);
+        for axis in 0..Self::DIMENSIONS {
+            points.push(self.offset(axis, -1));
+            points.push(self.offset(axis, 1));
+        }
+        points
+    }
+}
+
+impl Coordinates for Point {
+    const DIMENSIONS: usize = 2;
+
+    fn coordinate(&self, axis: usize) -> Position {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => panic!("No axis {} in 2 dimensions", axis),
+        }
+    }
+
+    fn from_fn<F: FnMut(usize) -> Position>(mut f: F) -> Self {
+        Point::new(f(0), f(1))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point3D {
+    pub x: Position,
+    pub y: Position,
+    pub z: Position,
+}
+
+impl Point3D {
+    pub fn new(x: Position, y: Position, z: Position) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl Coordinates for Point3D {
+    const DIMENSIONS: usize = 3;
+
+    fn coordinate(&self, axis: usize) -> Position {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            2 => self.z,
+            _ => panic!("No axis {} in 3 dimensions", axis),
+        }
+    }
+
+    fn from_fn<F: FnMut(usize) -> Position>(mut f: F) -> Self {
+        Self::new(f(0), f(1), f(2))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point4D {
+    pub x: Position,
+    pub y: Position,
+    pub z: Position,
+    pub w: Position,
+}
+
+impl Point4D {
+    pub fn new(x: Position, y: Position, z: Position, w: Position) -> Self {
+        Self { x, y, z, w }
+    }
+}
+
+impl Coordinates for Point4D {
+    const DIMENSIONS: usize = 4;
+
+    fn coordinate(&self, axis: usize) -> Position {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            2 => self.z,
+            3 => self.w,
+            _ => panic!("No axis {} in 4 dimensions", axis),
+        }
+    }
+
+    fn from_fn<F: FnMut(usize) -> Position>(mut f: F) -> Self {
+        Self::new(f(0), f(1), f(2), f(3))
+    }
+}
+
+/// Parse comma separated coordinates, optionally wrapped in `<...>`.
+fn parse_coordinates<P: Coordinates>(s: &str) -> Result<P, ParsePointError> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^\s*<?\s*(?P<values>[-\d\s,]+?)\s*>?\s*$").unwrap();
+    };
+
+    let cap = RE
+        .captures(s)
+        .ok_or_else(|| ParsePointError::InvalidLiteral(s.to_string()))?;
+
+    let values = cap["values"]
+        .split(',')
+        .map(|v| v.trim().parse::<Position>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if values.len() != P::DIMENSIONS {
+        return Err(ParsePointError::InvalidLiteral(s.to_string()));
+    }
+
+    Ok(P::from_fn(|a| values[a]))
+}
+
+impl FromStr for Point3D {
+    type Err = ParsePointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_coordinates(s)
+    }
+}
+
+impl FromStr for Point4D {
+    type Err = ParsePointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_coordinates(s)
+    }
+}
+
+impl fmt::Display for Point3D {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+impl fmt::Display for Point4D {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.z, self.w)
+    }
+}
+
+/// The smallest box containing a set of points, in any dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bounds<P> {
+    min: P,
+    max: P,
+}
+
+impl<P: Coordinates> Bounds<P> {
+    /// Bounds which contain nothing, until a point is included.
+    pub fn empty() -> Self {
+        Self {
+            min: P::from_fn(|_| Position::MAX),
+            max: P::from_fn(|_| Position::MIN),
+        }
+    }
+
+    pub fn from_corners(a: P, b: P) -> Self {
+        Self {
+            min: P::from_fn(|axis| a.coordinate(axis).min(b.coordinate(axis))),
+            max: P::from_fn(|axis| a.coordinate(axis).max(b.coordinate(axis))),
+        }
+    }
+
+    pub fn from_points(points: impl Iterator<Item = P>) -> Self {
+        let mut bounds = Self::empty();
+        for point in points {
+            bounds.include(point);
+        }
+        bounds
+    }
+
+    pub fn include(&mut self, point: P) {
+        let (min, max) = (self.min, self.max);
+        self.min = P::from_fn(|axis| min.coordinate(axis).min(point.coordinate(axis)));
+        self.max = P::from_fn(|axis| max.coordinate(axis).max(point.coordinate(axis)));
+    }
+
+    pub fn min(&self) -> P {
+        self.min
+    }
+
+    pub fn max(&self) -> P {
+        self.max
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..P::DIMENSIONS).any(|axis| self.min.coordinate(axis) > self.max.coordinate(axis))
+    }
+
+    pub fn contains(&self, point: P) -> bool {
+        (0..P::DIMENSIONS).all(|axis| {
+            self.min.coordinate(axis) <= point.coordinate(axis)
+                && point.coordinate(axis) <= self.max.coordinate(axis)
+        })
+    }
+
+    /// The points inside both bounds, if there are any.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let bounds = Self {
+            min: P::from_fn(|axis| self.min.coordinate(axis).max(other.min.coordinate(axis))),
+            max: P::from_fn(|axis| self.max.coordinate(axis).min(other.max.coordinate(axis))),
+        };
+        if bounds.is_empty() {
+            None
+        } else {
+            Some(bounds)
+        }
+    }
+
+    /// The Manhattan distance from a point to the nearest point inside
+    /// the bounds, which is zero for points inside.
+    pub fn manhattan_distance(&self, point: P) -> Position {
+        (0..P::DIMENSIONS)
+            .map(|axis| {
+                let p = point.coordinate(axis);
+                (self.min.coordinate(axis) - p)
+                    .max(p - self.max.coordinate(axis))
+                    .max(0)
+            })
+            .sum()
+    }
+
+    /// The number of points along an axis.
+    pub fn size(&self, axis: usize) -> u64 {
+        let size = i64::from(self.max.coordinate(axis)) - i64::from(self.min.coordinate(axis)) + 1;
+        size.max(0) as u64
+    }
+
+    /// The number of points inside the bounds, saturating at `u64::MAX`
+    /// for very large bounds.
+    pub fn volume(&self) -> u64 {
+        (0..P::DIMENSIONS).fold(1, |volume: u64, axis| {
+            volume.saturating_mul(self.size(axis))
+        })
+    }
+
+    /// The number of points along the widest axis.
+    pub fn longest_side(&self) -> u64 {
+        (0..P::DIMENSIONS)
+            .map(|axis| self.size(axis))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Every point inside the bounds, varying the first axis fastest.
+    pub fn points(&self) -> BoundsIterator<P> {
+        BoundsIterator {
+            bounds: *self,
+            next: if self.is_empty() {
+                None
+            } else {
+                Some(self.min)
+            },
+        }
+    }
+}
+
+pub struct BoundsIterator<P> {
+    bounds: Bounds<P>,
+    next: Option<P>,
+}
+
+impl<P: Coordinates> Iterator for BoundsIterator<P> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<P> {
+        let current = self.next?;
+
+        self.next = None;
+        for axis in 0..P::DIMENSIONS {
+            if current.coordinate(axis) < self.bounds.max.coordinate(axis) {
+                let min = self.bounds.min;
+                self.next = Some(P::from_fn(|a| {
+                    if a < axis {
+                        min.coordinate(a)
+                    } else if a == axis {
+                        current.coordinate(a) + 1
+                    } else {
+                        current.coordinate(a)
+                    }
+                }));
+                break;
+            }
+        }
+
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let p: Point3D = "<1,-2, 3>".parse().unwrap();
+        assert_eq!(p, Point3D::new(1, -2, 3));
+        assert_eq!(p.to_string().parse::<Point3D>().unwrap(), p);
+
+        let p: Point4D = " -1,2,3,-4".parse().unwrap();
+        assert_eq!(p, Point4D::new(-1, 2, 3, -4));
+
+        assert!("1,2".parse::<Point3D>().is_err());
+        assert!("<1,2,3,4,5>".parse::<Point4D>().is_err());
+        assert!("<1,a,3>".parse::<Point3D>().is_err());
+    }
+
+    #[test]
+    fn distances() {
+        let a = Point3D::new(0, 0, 0);
+        assert_eq!(a.manhattan_distance(Point3D::new(1, -2, 3)), 6);
+        assert_eq!(
+            Point4D::origin().manhattan_distance(Point4D::new(0, 3, 0, -3)),
+            6
+        );
+        assert_eq!(
+            Coordinates::manhattan_distance(Point::new(1, 1), Point::new(3, 0)),
+            3
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let neighbours = Point3D::origin().neighbours();
+        assert_eq!(neighbours.len(), 6);
+        assert!(neighbours.contains(&Point3D::new(0, 0, -1)));
+        assert!(neighbours
+            .iter()
+            .all(|n| n.manhattan_distance(Point3D::origin()) == 1));
+
+        assert_eq!(Point4D::origin().neighbours().len(), 8);
+    }
+
+    #[test]
+    fn bounds() {
+        let bounds =
+            Bounds::from_points(vec![Point3D::new(1, 0, 0), Point3D::new(0, 2, -1)].into_iter());
+        assert_eq!(bounds.min(), Point3D::new(0, 0, -1));
+        assert_eq!(bounds.max(), Point3D::new(1, 2, 0));
+        assert_eq!(bounds.volume(), 12);
+        assert_eq!(bounds.longest_side(), 3);
+        assert!(bounds.contains(Point3D::new(1, 1, -1)));
+        assert!(!bounds.contains(Point3D::new(1, 3, -1)));
+
+        let points: Vec<_> = bounds.points().collect();
+        assert_eq!(points.len(), 12);
+        assert_eq!(points[0], Point3D::new(0, 0, -1));
+        assert_eq!(points[1], Point3D::new(1, 0, -1));
+        assert_eq!(points[11], Point3D::new(1, 2, 0));
+
+        assert_eq!(bounds.manhattan_distance(Point3D::new(1, 1, -1)), 0);
+        assert_eq!(bounds.manhattan_distance(Point3D::new(3, -1, 0)), 3);
+        assert_eq!(
+            bounds.intersection(&Bounds::from_corners(
+                Point3D::new(1, 1, 1),
+                Point3D::new(-1, 5, -5)
+            )),
+            Some(Bounds::from_corners(
+                Point3D::new(0, 1, -1),
+                Point3D::new(1, 2, 0)
+            ))
+        );
+        assert_eq!(
+            bounds.intersection(&Bounds::from_corners(
+                Point3D::new(2, 0, 0),
+                Point3D::new(3, 0, 0)
+            )),
+            None
+        );
+
+        let empty: Bounds<Point4D> = Bounds::empty();
+        assert!(empty.is_empty());
+        assert_eq!(empty.volume(), 0);
+        assert_eq!(empty.points().count(), 0);
+
+        let huge = Bounds::from_corners(
+            Point3D::new(-300_000_000, -300_000_000, -300_000_000),
+            Point3D::new(300_000_000, 300_000_000, 300_000_000),
+        );
+        assert_eq!(huge.volume(), u64::MAX);
+        assert_eq!(huge.longest_side(), 600_000_001);
+    }
+}
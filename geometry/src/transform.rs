@@ -0,0 +1,191 @@
+//! Rotations and reflections of the plane.
+//!
+//! Transforms act about the origin, with `y` increasing downwards as it
+//! does on screen, so `RotateRight` turns `Direction::Up` into
+//! `Direction::Right`. Combine them with `translate` or `normalize` to
+//! move the result somewhere useful.
+//!
+//! The free functions here do the same for any collection of points,
+//! such as the open tiles of a map.
+
+use crate::{BoundingBox, Point, Position};
+
+/// One of the eight symmetries of a square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Identity,
+    RotateRight,
+    RotateHalf,
+    RotateLeft,
+    /// Mirror left to right, across the vertical axis.
+    FlipHorizontal,
+    /// Mirror top to bottom, across the horizontal axis.
+    FlipVertical,
+    /// Swap `x` and `y`, reflecting across the leading diagonal.
+    Transpose,
+    /// Reflect across the other diagonal.
+    AntiTranspose,
+}
+
+impl Transform {
+    /// Every transform, starting with the rotations.
+    pub fn all() -> impl Iterator<Item = Self> {
+        [
+            Transform::Identity,
+            Transform::RotateRight,
+            Transform::RotateHalf,
+            Transform::RotateLeft,
+            Transform::FlipHorizontal,
+            Transform::FlipVertical,
+            Transform::Transpose,
+            Transform::AntiTranspose,
+        ]
+        .iter()
+        .cloned()
+    }
+
+    pub fn apply(self, point: Point) -> Point {
+        let Point { x, y } = point;
+        match self {
+            Transform::Identity => Point::new(x, y),
+            Transform::RotateRight => Point::new(-y, x),
+            Transform::RotateHalf => Point::new(-x, -y),
+            Transform::RotateLeft => Point::new(y, -x),
+            Transform::FlipHorizontal => Point::new(-x, y),
+            Transform::FlipVertical => Point::new(x, -y),
+            Transform::Transpose => Point::new(y, x),
+            Transform::AntiTranspose => Point::new(-y, -x),
+        }
+    }
+
+    /// Apply this transform to each of `points`.
+    pub fn apply_all<I>(self, points: I) -> impl Iterator<Item = Point>
+    where
+        I: IntoIterator<Item = Point>,
+    {
+        points.into_iter().map(move |p| self.apply(p))
+    }
+
+    /// The box covering every transformed point of `bbox`.
+    pub fn apply_bbox(self, bbox: BoundingBox) -> BoundingBox {
+        if bbox.is_empty() {
+            return BoundingBox::empty();
+        }
+        BoundingBox::from_corners(
+            self.apply(Point::new(bbox.left(), bbox.top())),
+            self.apply(Point::new(bbox.right(), bbox.bottom())),
+        )
+    }
+
+    /// The transform which undoes this one.
+    pub fn inverse(self) -> Self {
+        match self {
+            Transform::RotateRight => Transform::RotateLeft,
+            Transform::RotateLeft => Transform::RotateRight,
+            other => other,
+        }
+    }
+
+    /// Apply this transform, followed by `other`.
+    pub fn then(self, other: Self) -> Self {
+        let x = other.apply(self.apply(Point::new(1, 0)));
+        let y = other.apply(self.apply(Point::new(0, 1)));
+        Transform::all()
+            .find(|t| t.apply(Point::new(1, 0)) == x && t.apply(Point::new(0, 1)) == y)
+            .expect("Symmetries of a square are closed under composition")
+    }
+}
+
+/// Move each of `points` by the same offset.
+pub fn translate<I>(points: I, dx: Position, dy: Position) -> impl Iterator<Item = Point>
+where
+    I: IntoIterator<Item = Point>,
+{
+    points.into_iter().map(move |p| p.translate(dx, dy))
+}
+
+/// The points which are inside `bbox`.
+pub fn crop<I>(points: I, bbox: BoundingBox) -> impl Iterator<Item = Point>
+where
+    I: IntoIterator<Item = Point>,
+{
+    points.into_iter().filter(move |p| bbox.contains(*p))
+}
+
+/// Move the points so the top left of their bounding box is at the origin.
+pub fn normalize<I>(points: I) -> impl Iterator<Item = Point>
+where
+    I: IntoIterator<Item = Point>,
+{
+    let points: Vec<Point> = points.into_iter().collect();
+    let origin = BoundingBox::from_points(points.iter().cloned()).origin();
+    translate(points, -origin.x, -origin.y)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Direction;
+
+    #[test]
+    fn rotations() {
+        let up = Point::new(0, 0).step(Direction::Up);
+        let right = Point::new(0, 0).step(Direction::Right);
+        assert_eq!(Transform::RotateRight.apply(up), right);
+        assert_eq!(Transform::RotateLeft.apply(right), up);
+
+        let point = Point::new(2, -5);
+        assert_eq!(
+            Transform::RotateRight.then(Transform::RotateRight),
+            Transform::RotateHalf
+        );
+        assert_eq!(
+            Transform::RotateLeft.then(Transform::FlipHorizontal),
+            Transform::AntiTranspose
+        );
+        for t in Transform::all() {
+            assert_eq!(t.inverse().apply(t.apply(point)), point);
+            assert_eq!(t.then(t.inverse()), Transform::Identity);
+        }
+    }
+
+    #[test]
+    fn bbox() {
+        let bbox = BoundingBox::new(1, 3, -1, 0);
+        assert_eq!(
+            Transform::RotateRight.apply_bbox(bbox),
+            BoundingBox::new(0, 1, 1, 3)
+        );
+        assert_eq!(
+            Transform::FlipHorizontal.apply_bbox(bbox),
+            BoundingBox::new(-3, -1, -1, 0)
+        );
+        assert!(Transform::Transpose
+            .apply_bbox(BoundingBox::empty())
+            .is_empty());
+    }
+
+    #[test]
+    fn point_sets() {
+        let points = vec![Point::new(2, 3), Point::new(4, 3), Point::new(2, 6)];
+
+        let rotated: Vec<_> = Transform::RotateRight.apply_all(points.clone()).collect();
+        assert_eq!(
+            rotated,
+            vec![Point::new(-3, 2), Point::new(-3, 4), Point::new(-6, 2)]
+        );
+
+        let normalized: Vec<_> = normalize(rotated).collect();
+        assert_eq!(
+            normalized,
+            vec![Point::new(3, 0), Point::new(3, 2), Point::new(0, 0)]
+        );
+
+        let cropped: Vec<_> = crop(points.clone(), BoundingBox::new(0, 3, 0, 9)).collect();
+        assert_eq!(cropped, vec![Point::new(2, 3), Point::new(2, 6)]);
+
+        let moved: Vec<_> = translate(points, -2, 1).collect();
+        assert_eq!(moved[2], Point::new(0, 7));
+    }
+}
@@ -0,0 +1,260 @@
+//! Partitioning an area between seed points, by which seed is nearest.
+
+use crate::grid::Grid;
+use crate::kdtree::KDTree;
+use crate::{BoundingBox, Point};
+
+/// A way of measuring the distance between two points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// The sum of the distances along each axis.
+    Manhattan,
+    /// The largest distance along either axis.
+    Chebyshev,
+    /// The square of the straight line distance, which orders points
+    /// the same way without leaving the integers.
+    SquaredEuclidean,
+}
+
+impl Metric {
+    pub fn distance(self, a: Point, b: Point) -> i64 {
+        let dx = (i64::from(a.x) - i64::from(b.x)).abs();
+        let dy = (i64::from(a.y) - i64::from(b.y)).abs();
+        match self {
+            Metric::Manhattan => dx + dy,
+            Metric::Chebyshev => dx.max(dy),
+            Metric::SquaredEuclidean => dx * dx + dy * dy,
+        }
+    }
+
+    /// The sum of the distances from `point` to each seed.
+    pub fn total_distance(self, point: Point, seeds: &[Point]) -> i64 {
+        seeds.iter().map(|s| self.distance(point, *s)).sum()
+    }
+}
+
+/// Which seed is nearest to each point in an area. Points which are
+/// equally near to more than one seed belong to none of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Voronoi {
+    seeds: Vec<Point>,
+    metric: Metric,
+    owners: Grid<Option<usize>>,
+    areas: Vec<usize>,
+    unbounded: Vec<bool>,
+}
+
+/// A grid over `area` with each point set by `f`, filled in parallel
+/// when rayon is available.
+#[cfg(feature = "rayon")]
+fn scan<T, F>(area: BoundingBox, f: F) -> Grid<T>
+where
+    T: Send,
+    F: Fn(Point) -> T + Sync + Send,
+{
+    Grid::par_from_fn(area, f)
+}
+
+#[cfg(not(feature = "rayon"))]
+fn scan<T, F>(area: BoundingBox, f: F) -> Grid<T>
+where
+    T: Send,
+    F: Fn(Point) -> T + Sync + Send,
+{
+    Grid::from_fn(area, f)
+}
+
+/// The index of the seed nearest to `point`, found by measuring the
+/// distance to every seed.
+fn nearest(metric: Metric, point: Point, seeds: &[Point]) -> Option<usize> {
+    let mut nearest = None;
+    let mut best = i64::MAX;
+    for (i, seed) in seeds.iter().enumerate() {
+        let distance = metric.distance(point, *seed);
+        if distance < best {
+            best = distance;
+            nearest = Some(i);
+        } else if distance == best {
+            nearest = None;
+        }
+    }
+    nearest
+}
+
+impl Voronoi {
+    /// Partition `area` between the seeds. Manhattan partitions look up
+    /// each point in a KD-tree of the seeds, while other metrics measure
+    /// the distance to every seed.
+    pub fn new(area: BoundingBox, seeds: Vec<Point>, metric: Metric) -> Self {
+        let owners = match metric {
+            Metric::Manhattan => {
+                let tree: KDTree<Point, usize> = seeds.iter().cloned().zip(0..).collect();
+                scan(area, |point| tree.nearest(point)?.unique().map(|(_, i)| *i))
+            }
+            _ => scan(area, |point| nearest(metric, point, &seeds)),
+        };
+
+        let mut areas = vec![0; seeds.len()];
+        let mut unbounded = vec![false; seeds.len()];
+        for (point, owner) in owners.iter() {
+            if let Some(i) = owner {
+                areas[*i] += 1;
+                unbounded[*i] |= area.is_edge(point);
+            }
+        }
+
+        Self {
+            seeds,
+            metric,
+            owners,
+            areas,
+            unbounded,
+        }
+    }
+
+    pub fn seeds(&self) -> &[Point] {
+        &self.seeds
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// The index of the seed nearest to `point`, unless it is tied or
+    /// outside the area.
+    pub fn owner(&self, point: Point) -> Option<usize> {
+        self.owners.get(point).and_then(|o| *o)
+    }
+
+    /// Whether `point` is in the area and equally near several seeds.
+    pub fn is_tied(&self, point: Point) -> bool {
+        self.owners.get(point) == Some(&None)
+    }
+
+    /// The owner of every point in the area.
+    pub fn owners(&self) -> &Grid<Option<usize>> {
+        &self.owners
+    }
+
+    /// The number of points nearest to a seed.
+    pub fn area(&self, seed: usize) -> usize {
+        self.areas[seed]
+    }
+
+    /// Whether a seed's region reaches the edge of the area, and so
+    /// might carry on beyond it.
+    pub fn is_unbounded(&self, seed: usize) -> bool {
+        self.unbounded[seed]
+    }
+
+    /// The seed with the largest region which doesn't reach the edge,
+    /// along with its area.
+    pub fn largest_bounded(&self) -> Option<(usize, usize)> {
+        (0..self.seeds.len())
+            .filter(|&i| !self.is_unbounded(i))
+            .map(|i| (i, self.area(i)))
+            .rev()
+            .max_by_key(|&(_, area)| area)
+    }
+}
+
+/// Mark the points in `area` whose total distance to the seeds is less
+/// than `limit`.
+pub fn total_distance_region(
+    area: BoundingBox,
+    seeds: &[Point],
+    metric: Metric,
+    limit: i64,
+) -> Grid<bool> {
+    scan(area, |point| metric.total_distance(point, seeds) < limit)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ascii::render;
+
+    fn seeds() -> Vec<Point> {
+        vec![
+            Point::new(1, 1),
+            Point::new(1, 6),
+            Point::new(8, 3),
+            Point::new(3, 4),
+            Point::new(5, 5),
+            Point::new(8, 9),
+        ]
+    }
+
+    #[test]
+    fn metrics() {
+        let (a, b) = (Point::new(1, -2), Point::new(4, 2));
+        assert_eq!(Metric::Manhattan.distance(a, b), 7);
+        assert_eq!(Metric::Chebyshev.distance(a, b), 4);
+        assert_eq!(Metric::SquaredEuclidean.distance(a, b), 25);
+        assert_eq!(Metric::Manhattan.total_distance(a, &[a, b, b]), 14);
+    }
+
+    #[test]
+    fn manhattan() {
+        let seeds = seeds();
+        let area = BoundingBox::from_points(seeds.iter().cloned());
+        let voronoi = Voronoi::new(area.margin(1), seeds, Metric::Manhattan);
+
+        assert_eq!(voronoi.owner(Point::new(0, 3)), Some(0));
+        assert!(voronoi.is_tied(Point::new(0, 4)));
+        assert_eq!(voronoi.owner(Point::new(20, 20)), None);
+        assert!(!voronoi.is_tied(Point::new(20, 20)));
+
+        assert!(voronoi.is_unbounded(0));
+        assert!(!voronoi.is_unbounded(3));
+        assert_eq!(voronoi.area(3), 9);
+        assert_eq!(voronoi.largest_bounded(), Some((4, 17)));
+
+        let region = total_distance_region(area, voronoi.seeds(), Metric::Manhattan, 32);
+        assert_eq!(region.values().filter(|v| **v).count(), 16);
+    }
+
+    #[test]
+    fn manhattan_against_every_seed() {
+        let mut seeds = seeds();
+        seeds.push(Point::new(5, 5));
+        seeds.push(Point::new(-2, 7));
+        let area = BoundingBox::from_points(seeds.iter().cloned()).margin(4);
+        let voronoi = Voronoi::new(area, seeds.clone(), Metric::Manhattan);
+
+        for point in area.points() {
+            assert_eq!(
+                voronoi.owner(point),
+                nearest(Metric::Manhattan, point, &seeds),
+                "{}",
+                point
+            );
+        }
+        assert!(voronoi.is_tied(Point::new(5, 5)));
+    }
+
+    #[test]
+    fn other_metrics() {
+        let seeds = vec![Point::new(0, 0), Point::new(4, 2)];
+        let area = BoundingBox::new(0, 4, 0, 2);
+        let label = |o: &Option<usize>| match o {
+            Some(i) => (b'a' + *i as u8) as char,
+            None => '.',
+        };
+
+        let chebyshev = Voronoi::new(area, seeds.clone(), Metric::Chebyshev);
+        assert_eq!(
+            render(chebyshev.owners(), |_, o| label(o)),
+            "aa.bb\naa.bb\naa.bb\n"
+        );
+
+        let euclidean = Voronoi::new(area, seeds, Metric::SquaredEuclidean);
+        assert_eq!(
+            render(euclidean.owners(), |_, o| label(o)),
+            "aaabb\naa.bb\naabbb\n"
+        );
+        assert_eq!(euclidean.area(0), 7);
+        assert_eq!(euclidean.largest_bounded(), None);
+    }
+}
@@ -9,7 +9,7 @@ use std::fs;
 use std::io;
 use std::io::prelude::*;
 use std::path::PathBuf;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::time;
 
@@ -25,7 +25,7 @@ use goblinwars::map::{Map, MapBuilder};
 use goblinwars::sprite::{Health, Species, SpriteBuilder};
 use goblinwars::views::{MapView, MessageView};
 use goblinwars::CombatExample;
-use goblinwars::{Game, GameOutcome};
+use goblinwars::{Event, Game, GameOutcome, Replay};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "wars", about = "Play a goblin wars scenario.")]
@@ -43,6 +43,18 @@ struct Opt {
     #[structopt(short = "a", long = "attack", default_value = "3")]
     attack: Health,
 
+    /// Scrub through a replay file instead of playing
+    #[structopt(short = "r", long = "replay")]
+    replay: bool,
+
+    /// Round to start the replay from
+    #[structopt(long = "round", default_value = "0")]
+    round: usize,
+
+    /// Write a replay of the game to this file
+    #[structopt(long = "record", parse(from_os_str))]
+    record: Option<PathBuf>,
+
     /// Input file
     #[structopt(parse(from_os_str))]
     input: PathBuf,
@@ -57,6 +69,10 @@ fn load(path: &PathBuf) -> io::Result<String> {
 fn main() -> Result<(), Error> {
     let opt = Opt::from_args();
 
+    if opt.replay {
+        return replay(&opt);
+    }
+
     let builder = MapBuilder::new(SpriteBuilder::new().with_attack(Species::Elf, opt.attack));
 
     let map = if opt.example {
@@ -84,7 +100,8 @@ fn main() -> Result<(), Error> {
             .child(MapView::new(rx, map.clone()).full_screen()),
     );
 
-    thread::spawn(move || worker(map, link, delay));
+    let record = opt.record.clone();
+    thread::spawn(move || worker(map, link, delay, record));
 
     siv.run();
 
@@ -97,8 +114,13 @@ struct Link {
     message: mpsc::Sender<String>,
 }
 
-fn worker(map: Map, link: Link, delay: time::Duration) {
-    let mut game = Game::new(map);
+fn worker(map: Map, link: Link, delay: time::Duration, record: Option<PathBuf>) {
+    let mut game = Game::new(map.clone());
+    let (tx_event, rx_event) = mpsc::channel();
+    if record.is_some() {
+        game.subscribe(move |event: &Event| tx_event.send(*event).unwrap_or(()));
+    }
+
     let result = game.run(|m, t| {
         thread::sleep(delay);
         link.map
@@ -113,9 +135,82 @@ fn worker(map: Map, link: Link, delay: time::Duration) {
     link.map.send(game.map().clone()).unwrap();
     thread::sleep(delay);
 
+    if let Some(path) = record {
+        let mut replay = Replay::new(map);
+        replay.extend(rx_event.try_iter());
+        if let Err(e) = fs::write(&path, replay.to_string()) {
+            link.message
+                .send(format!("Error writing replay: {}", e))
+                .unwrap();
+            return;
+        }
+    }
+
     match result {
         Ok(GameOutcome::Complete(outcome)) => link.message.send(outcome.to_string()).unwrap(),
         Ok(GameOutcome::Stopped) => link.message.send("Game stopped".to_string()).unwrap(),
         Err(e) => link.message.send(format!("Error: {}", e)).unwrap(),
     };
 }
+
+/// Shows one round of a replay at a time.
+#[derive(Debug)]
+struct Scrubber {
+    maps: Vec<Map>,
+    round: usize,
+    link: Link,
+}
+
+impl Scrubber {
+    fn step(&mut self, delta: isize) {
+        let last = self.maps.len() as isize - 1;
+        self.round = (self.round as isize + delta).max(0).min(last) as usize;
+        self.show();
+    }
+
+    fn show(&self) {
+        self.link.map.send(self.maps[self.round].clone()).unwrap();
+        self.link
+            .message
+            .send(format!(
+                "Round: {} of {} (, and . to step, [ and ] to jump)",
+                self.round,
+                self.maps.len() - 1
+            ))
+            .unwrap();
+    }
+}
+
+fn replay(opt: &Opt) -> Result<(), Error> {
+    let maps = load(&opt.input)?.parse::<Replay>()?.maps()?;
+    let round = opt.round.min(maps.len() - 1);
+
+    let (tx, rx) = mpsc::channel();
+    let (tx_message, rx_message) = mpsc::channel();
+    let link = Link {
+        map: tx,
+        message: tx_message,
+    };
+
+    let mut siv = Cursive::default();
+    siv.set_fps(30);
+    siv.add_global_callback('q', Cursive::quit);
+    siv.load_toml(include_str!("theme.toml")).unwrap();
+    siv.add_layer(
+        LinearLayout::vertical()
+            .child(TextView::new(format!("Replay: {:?}", opt.input)).fixed_height(1))
+            .child(MessageView::new(rx_message).fixed_height(1))
+            .child(MapView::new(rx, maps[round].clone()).full_screen()),
+    );
+
+    let scrubber = Arc::new(Mutex::new(Scrubber { maps, round, link }));
+    scrubber.lock().unwrap().show();
+    for &(key, delta) in &[(',', -1), ('.', 1), ('[', -10), (']', 10)] {
+        let scrubber = scrubber.clone();
+        siv.add_global_callback(key, move |_| scrubber.lock().unwrap().step(delta));
+    }
+
+    siv.run();
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+
+use geometry::Point;
+
+use crate::sprite::{Health, Species};
+
+/// Something which happened during a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A sprite took a step.
+    Moved { from: Point, to: Point },
+
+    /// A sprite attacked an enemy, taking away `damage` health and
+    /// leaving it with `remaining`.
+    Attacked {
+        attacker: Point,
+        target: Point,
+        damage: Health,
+        remaining: Health,
+    },
+
+    /// A sprite was killed, and removed from the map.
+    Died { at: Point, species: Species },
+
+    /// Every sprite has had its turn in round `n`.
+    RoundEnded { n: u32 },
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Moved { from, to } => write!(f, "{} moved to {}", from, to),
+            Event::Attacked {
+                attacker,
+                target,
+                damage,
+                remaining,
+            } => write!(
+                f,
+                "{} hit {} for {} ({} left)",
+                attacker, target, damage, remaining
+            ),
+            Event::Died { at, species } => write!(f, "{} died at {}", species, at),
+            Event::RoundEnded { n } => write!(f, "Round {} ended", n),
+        }
+    }
+}
+
+/// Receives each event as it happens.
+pub trait Observer {
+    fn notify(&mut self, event: &Event);
+}
+
+impl<F> Observer for F
+where
+    F: FnMut(&Event),
+{
+    fn notify(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+/// The observers subscribed to a game.
+///
+/// Observers belong to a single game, so cloning produces an empty set.
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<Box<dyn Observer + Send>>,
+}
+
+impl Observers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe<O>(&mut self, observer: O)
+    where
+        O: Observer + Send + 'static,
+    {
+        self.observers.push(Box::new(observer));
+    }
+}
+
+impl Observer for Observers {
+    fn notify(&mut self, event: &Event) {
+        for observer in self.observers.iter_mut() {
+            observer.notify(event);
+        }
+    }
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observers({})", self.observers.len())
+    }
+}
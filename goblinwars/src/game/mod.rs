@@ -5,7 +5,9 @@ use crate::map::Map;
 use crate::map::Pathfinders;
 use crate::sprite::{Health, Species};
 
+pub mod event;
 pub mod round;
+use self::event::{Event, Observer, Observers};
 use self::round::{Round, RoundError, RoundOutcome};
 
 #[derive(Debug)]
@@ -37,6 +39,9 @@ impl fmt::Display for GameComplete {
 pub struct Game {
     map: Map,
     pathfinders: Pathfinders,
+    observers: Observers,
+    protected: Option<Species>,
+    rounds: u32,
 }
 
 impl Game {
@@ -44,42 +49,74 @@ impl Game {
         Self {
             map: map,
             pathfinders: Pathfinders::new(),
+            observers: Observers::new(),
+            protected: None,
+            rounds: 0,
         }
     }
 
+    /// Stop the game as soon as a sprite of `species` dies.
+    pub fn with_protected(mut self, species: Species) -> Self {
+        self.protected = Some(species);
+        self
+    }
+
     pub fn map(&self) -> &Map {
         &self.map
     }
 
+    /// The number of full rounds played so far.
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    /// Send every event from this game to `observer`.
+    pub fn subscribe<O>(&mut self, observer: O)
+    where
+        O: Observer + Send + 'static,
+    {
+        self.observers.subscribe(observer);
+    }
+
     pub fn round(&mut self) -> Round {
-        Round::new(&mut self.map, &mut self.pathfinders)
+        let round =
+            Round::new(&mut self.map, &mut self.pathfinders).with_observer(&mut self.observers);
+        match self.protected {
+            Some(species) => round.with_protected(species),
+            None => round,
+        }
+    }
+
+    /// Play a single round, counting it if every sprite got a turn.
+    pub fn play_round(&mut self) -> RoundOutcome {
+        let outcome = self.round().play();
+        match outcome {
+            RoundOutcome::MidRoundVictory(_) | RoundOutcome::Stopped => return outcome,
+            _ => {}
+        }
+        self.rounds += 1;
+        self.observers.notify(&Event::RoundEnded { n: self.rounds });
+        outcome
     }
 
     pub fn run<F>(&mut self, mut f: F) -> Result<GameOutcome, RoundError>
     where
         F: FnMut(&Self, u32) -> Result<(), Error>,
     {
-        for round in 1.. {
-            f(&self, round).map_err(RoundError::Interrupted)?;
-            match self.round().play() {
-                RoundOutcome::Victory(s) => {
-                    return Ok(GameOutcome::Complete(GameComplete {
-                        rounds: round,
-                        victors: s,
-                        score: round * self.map.score(),
-                    }));
-                }
-                RoundOutcome::MidRoundVictory(s) => {
+        loop {
+            f(self, self.rounds + 1).map_err(RoundError::Interrupted)?;
+            match self.play_round() {
+                RoundOutcome::Victory(s) | RoundOutcome::MidRoundVictory(s) => {
                     return Ok(GameOutcome::Complete(GameComplete {
-                        rounds: round - 1,
+                        rounds: self.rounds,
                         victors: s,
-                        score: (round - 1) * self.map.score(),
+                        score: self.rounds * self.map.score(),
                     }));
                 }
+                RoundOutcome::Stopped => return Ok(GameOutcome::Stopped),
                 RoundOutcome::NoAction => return Err(RoundError::NoMovesRemain),
                 _ => {}
             }
         }
-        unreachable!()
     }
 }
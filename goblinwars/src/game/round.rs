@@ -9,6 +9,8 @@ use crate::map::Map;
 use crate::map::Pathfinders;
 use crate::sprite::{Species, SpriteStatus};
 
+use super::event::{Event, Observer};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RoundOutcome {
     NoAction,
@@ -17,6 +19,7 @@ pub enum RoundOutcome {
     Movement,
     MidRoundVictory(Species),
     Victory(Species),
+    Stopped,
 }
 
 impl RoundOutcome {
@@ -95,6 +98,8 @@ pub struct Round<'m> {
     map: &'m mut Map,
     pathfinder: &'m mut Pathfinders,
     queue: BinaryHeap<QPoint>,
+    observer: Option<&'m mut dyn Observer>,
+    protected: Option<Species>,
 }
 
 impl<'m> Round<'m> {
@@ -104,6 +109,26 @@ impl<'m> Round<'m> {
             map,
             queue,
             pathfinder,
+            observer: None,
+            protected: None,
+        }
+    }
+
+    /// Report each move, attack and death to `observer`.
+    pub fn with_observer(mut self, observer: &'m mut dyn Observer) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Stop the round as soon as a sprite of `species` dies.
+    pub fn with_protected(mut self, species: Species) -> Self {
+        self.protected = Some(species);
+        self
+    }
+
+    fn emit(&mut self, event: Event) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.notify(&event);
         }
     }
 
@@ -151,6 +176,10 @@ impl<'m> Round<'m> {
                 self.map.sprites.step(location, d);
                 outcome = outcome.movement();
                 self.pathfinder.clear();
+                self.emit(Event::Moved {
+                    from: location,
+                    to: location.step(d),
+                });
                 location.step(d)
             } else {
                 location
@@ -158,11 +187,33 @@ impl<'m> Round<'m> {
 
             // Next, the attack phase
             if let Some(target) = self.map.target(location) {
-                outcome = match self.map.sprites.attack(location, target) {
+                // A killing blow only removes the health the target had left.
+                let attack = self.map.sprites.get(location).map_or(0, |s| s.attack());
+                let health = self.map.sprites.get(target).map_or(0, |s| s.health());
+                let damage = attack.min(health);
+                let status = self.map.sprites.attack(location, target);
+                self.emit(Event::Attacked {
+                    attacker: location,
+                    target,
+                    damage,
+                    remaining: match status {
+                        SpriteStatus::Alive(_, health) => health,
+                        SpriteStatus::Dead(_) => 0,
+                    },
+                });
+                outcome = match status {
                     SpriteStatus::Alive(_, _) => outcome.combat(),
                     SpriteStatus::Dead(species) => {
                         self.pathfinder.clear();
-                        outcome.casualty(species)
+                        self.emit(Event::Died {
+                            at: target,
+                            species,
+                        });
+                        if self.protected == Some(species) {
+                            RoundOutcome::Stopped
+                        } else {
+                            outcome.casualty(species)
+                        }
                     }
                 };
             }
@@ -176,8 +227,11 @@ impl<'m> Round<'m> {
 mod tests {
     use super::*;
 
+    use std::sync::mpsc;
+
     use crate::game::Game;
     use crate::map::MapBuilder;
+    use crate::sprite::SpriteBuilder;
 
     use crate::examples::map_ascii_trim;
 
@@ -242,4 +296,59 @@ mod tests {
         assert_eq!(trim(maps[3]), trim(&game.map().to_string()));
     }
 
+    #[test]
+    fn events() {
+        let builder = MapBuilder::new(SpriteBuilder::new().with_health(Species::Goblin, 4));
+        let mut game = Game::new(builder.build("#####\n#E.G#\n#####").unwrap());
+
+        let (tx, rx) = mpsc::channel();
+        game.subscribe(move |event: &Event| tx.send(*event).unwrap());
+
+        let (elf, goblin) = (Point::new(2, 1), Point::new(3, 1));
+        game.play_round();
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![
+                Event::Moved {
+                    from: Point::new(1, 1),
+                    to: elf
+                },
+                Event::Attacked {
+                    attacker: elf,
+                    target: goblin,
+                    damage: 3,
+                    remaining: 1
+                },
+                Event::Attacked {
+                    attacker: goblin,
+                    target: elf,
+                    damage: 3,
+                    remaining: 197
+                },
+                Event::RoundEnded { n: 1 },
+            ]
+        );
+
+        assert_eq!(
+            game.play_round(),
+            RoundOutcome::MidRoundVictory(Species::Elf)
+        );
+        assert_eq!(game.rounds(), 1);
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![
+                Event::Attacked {
+                    attacker: elf,
+                    target: goblin,
+                    damage: 1,
+                    remaining: 0
+                },
+                Event::Died {
+                    at: goblin,
+                    species: Species::Goblin
+                },
+            ]
+        );
+    }
+
 }
@@ -3,8 +3,13 @@
 mod examples;
 mod game;
 pub mod map;
+pub mod replay;
+pub mod search;
 pub mod sprite;
 pub mod views;
 
 pub use self::examples::CombatExample;
+pub use self::game::event::{Event, Observer};
 pub use self::game::{Game, GameOutcome};
+pub use self::replay::Replay;
+pub use self::search::FlawlessSearch;
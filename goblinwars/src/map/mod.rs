@@ -117,10 +117,8 @@ impl Map {
 impl fmt::Display for Map {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let bbox = self.bbox().margin(1);
-        for y in bbox.vertical() {
-            for x in bbox.horizontal() {
-                let point = Point::new(x, y);
-
+        for row in bbox.rows() {
+            for point in row {
                 if let Some(sprite) = self.sprites.get(point) {
                     write!(f, "{}", sprite.glyph())?;
                 } else {
@@ -142,11 +140,9 @@ impl<'m> fmt::Display for MapStatusView<'m> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let bbox = self.map.bbox().margin(1);
         let mut sprites = Vec::new();
-        for y in bbox.vertical() {
+        for row in bbox.rows() {
             sprites.clear();
-            for x in bbox.horizontal() {
-                let point = Point::new(x, y);
-
+            for point in row {
                 if let Some(sprite) = self.map.sprites.get(point) {
                     write!(f, "{}", sprite.glyph())?;
                     sprites.push(sprite.info());
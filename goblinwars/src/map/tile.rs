@@ -4,7 +4,8 @@ use std::str::FromStr;
 
 use failure::Fail;
 
-use geometry::{BoundingBox, Point};
+use geometry::transform::{self, Transform};
+use geometry::{BoundingBox, Point, Position};
 
 #[derive(Debug, Clone, PartialEq, Eq, Fail)]
 pub enum ParseTileError {
@@ -92,6 +93,26 @@ impl Grid {
         }
         bbox
     }
+
+    /// Rotate or reflect the open tiles about the origin.
+    pub fn transform(&self, transform: Transform) -> Self {
+        Grid(transform.apply_all(self.0.iter().cloned()).collect())
+    }
+
+    pub fn translate(&self, dx: Position, dy: Position) -> Self {
+        Grid(transform::translate(self.0.iter().cloned(), dx, dy).collect())
+    }
+
+    /// Keep only the open tiles inside `bbox`.
+    pub fn crop(&self, bbox: BoundingBox) -> Self {
+        Grid(transform::crop(self.0.iter().cloned(), bbox).collect())
+    }
+
+    /// Move the open tiles so the top left of their bounding box is
+    /// at the origin.
+    pub fn normalize(&self) -> Self {
+        Grid(transform::normalize(self.0.iter().cloned()).collect())
+    }
 }
 
 #[cfg(test)]
@@ -126,4 +147,20 @@ mod tests {
         assert!(g.insert(position, Tile::Wall));
         assert_eq!(g.get(position), Tile::Wall);
     }
+
+    #[test]
+    fn transform_grid() {
+        let mut g = Grid::new();
+        g.insert(Point::new(2, 1), Tile::Empty);
+        g.insert(Point::new(3, 1), Tile::Empty);
+        g.insert(Point::new(3, 2), Tile::Empty);
+
+        let rotated = g.transform(Transform::RotateRight).normalize();
+        assert_eq!(rotated.bbox(), BoundingBox::new(0, 1, 0, 1));
+        assert_eq!(rotated.get(Point::new(0, 1)), Tile::Empty);
+        assert_eq!(rotated.get(Point::new(0, 0)), Tile::Wall);
+
+        let cropped = g.crop(BoundingBox::new(0, 2, 0, 5));
+        assert_eq!(cropped.bbox(), BoundingBox::new(2, 2, 1, 1));
+    }
 }
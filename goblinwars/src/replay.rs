@@ -0,0 +1,436 @@
+//! Recording games, and replaying them without running the simulation.
+//!
+//! A replay file starts with the map, followed by the health and attack
+//! of each sprite, and then every event from the game, one per line:
+//!
+//! ```text
+//! goblinwars replay
+//! map
+//! #####
+//! #E.G#
+//! #####
+//! end
+//! sprite 1,1 E 200 3
+//! sprite 3,1 G 200 3
+//! moved 1,1 2,1
+//! attacked 2,1 3,1 3 197
+//! attacked 3,1 2,1 3 197
+//! ended 1
+//! ```
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::sync::mpsc;
+
+use failure::Fail;
+
+use geometry::{BoundingBox, ParsePointError, Point};
+
+use crate::game::event::Event;
+use crate::game::round::RoundError;
+use crate::game::{Game, GameOutcome};
+use crate::map::{Map, MapBuilder, MapElement, ParseMapError};
+use crate::sprite::{ParseSpeciesError, Sprite};
+
+const HEADER: &str = "goblinwars replay";
+
+#[derive(Debug, Fail)]
+pub enum ReplayError {
+    #[fail(display = "No sprite at {}", _0)]
+    MissingSprite(Point),
+
+    #[fail(display = "Can't move onto {}, it is occupied", _0)]
+    Occupied(Point),
+
+    #[fail(display = "The replay has no round {}", _0)]
+    NoSuchRound(u32),
+}
+
+#[derive(Debug, Fail)]
+pub enum ParseReplayError {
+    #[fail(display = "Missing replay header")]
+    MissingHeader,
+
+    #[fail(display = "Missing map")]
+    MissingMap,
+
+    #[fail(display = "Invalid Map: {}", _0)]
+    InvalidMap(ParseMapError),
+
+    #[fail(display = "Invalid Point: {}", _0)]
+    InvalidPoint(ParsePointError),
+
+    #[fail(display = "Invalid number: {}", _0)]
+    InvalidNumber(ParseIntError),
+
+    #[fail(display = "Invalid species: {}", _0)]
+    InvalidSpecies(ParseSpeciesError),
+
+    #[fail(display = "Sprite doesn't match the map at {}", _0)]
+    MismatchedSprite(Point),
+
+    #[fail(display = "Invalid replay line: {}", _0)]
+    InvalidLine(String),
+}
+
+impl From<ParseMapError> for ParseReplayError {
+    fn from(error: ParseMapError) -> Self {
+        ParseReplayError::InvalidMap(error)
+    }
+}
+
+impl From<ParsePointError> for ParseReplayError {
+    fn from(error: ParsePointError) -> Self {
+        ParseReplayError::InvalidPoint(error)
+    }
+}
+
+impl From<ParseIntError> for ParseReplayError {
+    fn from(error: ParseIntError) -> Self {
+        ParseReplayError::InvalidNumber(error)
+    }
+}
+
+impl From<ParseSpeciesError> for ParseReplayError {
+    fn from(error: ParseSpeciesError) -> Self {
+        ParseReplayError::InvalidSpecies(error)
+    }
+}
+
+/// Update `map` with the effect of a single event.
+fn apply(map: &mut Map, event: &Event) -> Result<(), ReplayError> {
+    match *event {
+        Event::Moved { from, to } => {
+            if map.sprites.contains(to) {
+                return Err(ReplayError::Occupied(to));
+            }
+            let sprite = map
+                .sprites
+                .remove(from)
+                .ok_or(ReplayError::MissingSprite(from))?;
+            map.sprites.place(to, sprite);
+        }
+        Event::Attacked {
+            attacker,
+            target,
+            remaining,
+            ..
+        } => {
+            if !map.sprites.contains(attacker) {
+                return Err(ReplayError::MissingSprite(attacker));
+            }
+            let sprite = map
+                .sprites
+                .remove(target)
+                .ok_or(ReplayError::MissingSprite(target))?;
+            map.sprites.place(target, sprite.with_health(remaining));
+        }
+        Event::Died { at, .. } => {
+            map.sprites
+                .remove(at)
+                .ok_or(ReplayError::MissingSprite(at))?;
+        }
+        Event::RoundEnded { .. } => {}
+    }
+    Ok(())
+}
+
+/// The starting map of a game, and everything which happened in it.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    map: Map,
+    events: Vec<Event>,
+}
+
+impl Replay {
+    pub fn new(map: Map) -> Self {
+        Self {
+            map,
+            events: Vec::new(),
+        }
+    }
+
+    /// Play a game on `map` to the end, recording every event.
+    pub fn record(map: Map) -> Result<(Self, GameOutcome), RoundError> {
+        let (tx, rx) = mpsc::channel();
+        let mut game = Game::new(map.clone());
+        game.subscribe(move |event: &Event| tx.send(*event).unwrap_or(()));
+        let outcome = game.run(|_, _| Ok(()))?;
+
+        let mut replay = Self::new(map);
+        replay.extend(rx.try_iter());
+        Ok((replay, outcome))
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// The map before the first round.
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// The number of full rounds recorded.
+    pub fn rounds(&self) -> u32 {
+        self.events
+            .iter()
+            .filter_map(|e| match e {
+                Event::RoundEnded { n } => Some(n),
+                _ => None,
+            })
+            .count() as u32
+    }
+
+    /// The map after `round` full rounds.
+    pub fn map_at(&self, round: u32) -> Result<Map, ReplayError> {
+        if round > self.rounds() {
+            return Err(ReplayError::NoSuchRound(round));
+        }
+
+        let mut map = self.map.clone();
+        let mut played = 0;
+        for event in &self.events {
+            if played == round {
+                break;
+            }
+            apply(&mut map, event)?;
+            if let Event::RoundEnded { .. } = event {
+                played += 1;
+            }
+        }
+        Ok(map)
+    }
+
+    /// The map once every event has happened, including those from a
+    /// final round which was cut short.
+    pub fn final_map(&self) -> Result<Map, ReplayError> {
+        let mut map = self.map.clone();
+        for event in &self.events {
+            apply(&mut map, event)?;
+        }
+        Ok(map)
+    }
+
+    /// The map at the start and after each full round, followed by the
+    /// final map if the last round was cut short.
+    pub fn maps(&self) -> Result<Vec<Map>, ReplayError> {
+        let mut map = self.map.clone();
+        let mut maps = vec![map.clone()];
+        let mut pending = false;
+        for event in &self.events {
+            apply(&mut map, event)?;
+            if let Event::RoundEnded { .. } = event {
+                maps.push(map.clone());
+                pending = false;
+            } else {
+                pending = true;
+            }
+        }
+        if pending {
+            maps.push(map);
+        }
+        Ok(maps)
+    }
+}
+
+impl Extend<Event> for Replay {
+    fn extend<I: IntoIterator<Item = Event>>(&mut self, iter: I) {
+        self.events.extend(iter);
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+
+        // Maps are written from the origin so that positions survive
+        // being parsed again.
+        writeln!(f, "map")?;
+        let bbox = self.map.bbox();
+        let area = if bbox.is_empty() {
+            BoundingBox::empty()
+        } else {
+            BoundingBox::from_corners(
+                Point::new(0, 0),
+                Point::new(bbox.right() + 1, bbox.bottom() + 1),
+            )
+        };
+        for y in area.vertical() {
+            for x in area.horizontal() {
+                match self.map.element(Point::new(x, y)) {
+                    MapElement::Sprite(species) => write!(f, "{}", species)?,
+                    MapElement::Tile(tile) => write!(f, "{}", tile)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "end")?;
+
+        for (position, sprite) in self.map.sprites.iter() {
+            writeln!(
+                f,
+                "sprite {} {} {} {}",
+                position,
+                sprite.species(),
+                sprite.health(),
+                sprite.attack()
+            )?;
+        }
+
+        for event in &self.events {
+            match event {
+                Event::Moved { from, to } => writeln!(f, "moved {} {}", from, to)?,
+                Event::Attacked {
+                    attacker,
+                    target,
+                    damage,
+                    remaining,
+                } => writeln!(
+                    f,
+                    "attacked {} {} {} {}",
+                    attacker, target, damage, remaining
+                )?,
+                Event::Died { at, species } => writeln!(f, "died {} {}", at, species)?,
+                Event::RoundEnded { n } => writeln!(f, "ended {}", n)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = ParseReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+
+        if lines.next() != Some(HEADER) {
+            return Err(ParseReplayError::MissingHeader);
+        }
+        if lines.next() != Some("map") {
+            return Err(ParseReplayError::MissingMap);
+        }
+
+        let rows: Vec<&str> = lines.by_ref().take_while(|l| *l != "end").collect();
+        let mut replay = Self::new(MapBuilder::default().build(&rows.join("\n"))?);
+
+        for line in lines {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let event = match parts.as_slice() {
+                ["sprite", at, species, health, attack] => {
+                    let at = at.parse()?;
+                    let sprite = Sprite::new(species.parse()?, health.parse()?, attack.parse()?);
+                    match replay.map.sprites.get(at) {
+                        Some(s) if s.species() == sprite.species() => {}
+                        _ => return Err(ParseReplayError::MismatchedSprite(at)),
+                    }
+                    replay.map.sprites.place(at, sprite);
+                    continue;
+                }
+                ["moved", from, to] => Event::Moved {
+                    from: from.parse()?,
+                    to: to.parse()?,
+                },
+                ["attacked", attacker, target, damage, remaining] => Event::Attacked {
+                    attacker: attacker.parse()?,
+                    target: target.parse()?,
+                    damage: damage.parse()?,
+                    remaining: remaining.parse()?,
+                },
+                ["died", at, species] => Event::Died {
+                    at: at.parse()?,
+                    species: species.parse()?,
+                },
+                ["ended", n] => Event::RoundEnded { n: n.parse()? },
+                _ => return Err(ParseReplayError::InvalidLine(line.to_string())),
+            };
+            replay.push(event);
+        }
+
+        Ok(replay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::examples::map_ascii_trim;
+    use crate::sprite::{Species, SpriteBuilder};
+
+    fn trim(s: &str) -> String {
+        map_ascii_trim(s)
+    }
+
+    #[test]
+    fn replay() {
+        let map = MapBuilder::default()
+            .build(include_str!("../examples/combat/initial.txt"))
+            .unwrap();
+        let (replay, outcome) = Replay::record(map.clone()).unwrap();
+
+        match outcome {
+            GameOutcome::Complete(stats) => assert_eq!(stats.rounds, 47),
+            GameOutcome::Stopped => panic!("Game stopped"),
+        }
+        assert_eq!(replay.rounds(), 47);
+        assert!(replay.map_at(48).is_err());
+
+        let mut game = Game::new(map);
+        for round in 0..=47 {
+            assert_eq!(
+                trim(&replay.map_at(round).unwrap().status().to_string()),
+                trim(&game.map().status().to_string()),
+                "replay mismatch on round {}",
+                round
+            );
+            game.round().play();
+        }
+
+        // Combat ends at the start of round 48, before anyone acts.
+        let maps = replay.maps().unwrap();
+        assert_eq!(maps.len(), 48);
+        assert_eq!(
+            maps[47].status().to_string(),
+            replay.final_map().unwrap().status().to_string()
+        );
+        assert_eq!(replay.final_map().unwrap().score(), 590);
+    }
+
+    #[test]
+    fn replay_file() {
+        let builder = MapBuilder::new(SpriteBuilder::new().with_health(Species::Goblin, 4));
+        let map = builder.build("#####\n#E.G#\n#####").unwrap();
+        let (replay, _) = Replay::record(map).unwrap();
+
+        let text = replay.to_string();
+        assert_eq!(
+            text,
+            "goblinwars replay\nmap\n#####\n#E.G#\n#####\nend\n\
+             sprite 1,1 E 200 3\nsprite 3,1 G 4 3\n\
+             moved 1,1 2,1\nattacked 2,1 3,1 3 1\nattacked 3,1 2,1 3 197\nended 1\n\
+             attacked 2,1 3,1 1 0\ndied 3,1 G\n"
+        );
+
+        let parsed: Replay = text.parse().unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.rounds(), 1);
+        assert_eq!(
+            parsed.map_at(1).unwrap().status().to_string(),
+            replay.map_at(1).unwrap().status().to_string()
+        );
+
+        assert!("map\n#E#\nend".parse::<Replay>().is_err());
+        assert!(text
+            .replace("sprite 1,1 E", "sprite 1,1 G")
+            .parse::<Replay>()
+            .is_err());
+        assert!(text.replace("ended", "finished").parse::<Replay>().is_err());
+    }
+}
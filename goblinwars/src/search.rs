@@ -0,0 +1,197 @@
+//! Searching for the weakest sprites which can still win without losses.
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use rayon::prelude::*;
+
+use crate::game::{Game, GameComplete, GameOutcome};
+use crate::map::Map;
+use crate::sprite::{Health, Species};
+
+/// The sprite stat to vary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    Attack,
+    Health,
+}
+
+/// How a single game in a search went.
+#[derive(Debug)]
+pub enum Trial {
+    /// The species won without losing a single sprite.
+    Flawless(GameComplete),
+
+    /// A sprite of the species died during the given round, so the game
+    /// was abandoned.
+    Casualty(u32),
+
+    /// The enemy won without a fight, as the species had no sprites.
+    Defeat(GameComplete),
+
+    /// The game ran out of moves before anyone won.
+    Stalled,
+}
+
+/// Every game played by a search, keyed by the stat value used.
+#[derive(Debug)]
+pub struct Search {
+    trials: BTreeMap<Health, Trial>,
+}
+
+impl Search {
+    /// The smallest stat value which won without losses, and that game.
+    pub fn minimum(&self) -> Option<(Health, &GameComplete)> {
+        self.trials.iter().find_map(|(value, trial)| match trial {
+            Trial::Flawless(stats) => Some((*value, stats)),
+            _ => None,
+        })
+    }
+
+    pub fn trials(&self) -> impl Iterator<Item = (Health, &Trial)> {
+        self.trials.iter().map(|(value, trial)| (*value, trial))
+    }
+}
+
+/// Finds the smallest value of a stat which lets a species win without
+/// losing any sprites.
+///
+/// Values are tried in parallel batches, lowest first, and the search
+/// stops after the first batch containing a flawless victory. Every value
+/// below the minimum is tried, so the result doesn't rely on victories
+/// getting easier as the stat grows.
+#[derive(Debug, Clone)]
+pub struct FlawlessSearch {
+    map: Map,
+    species: Species,
+    stat: Stat,
+    range: RangeInclusive<Health>,
+}
+
+impl FlawlessSearch {
+    /// Search the attack power of `species`, from 1 up to 200, enough to
+    /// kill a default sprite in a single blow.
+    pub fn new(map: Map, species: Species) -> Self {
+        Self {
+            map,
+            species,
+            stat: Stat::Attack,
+            range: 1..=200,
+        }
+    }
+
+    pub fn with_stat(mut self, stat: Stat) -> Self {
+        self.stat = stat;
+        self
+    }
+
+    pub fn with_range(mut self, range: RangeInclusive<Health>) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// The starting map, with every sprite of the species given `value`
+    /// for the searched stat.
+    fn map(&self, value: Health) -> Map {
+        let mut map = self.map.clone();
+        let positions: Vec<_> = map
+            .sprites
+            .iter()
+            .filter(|(_, s)| s.species() == self.species)
+            .map(|(p, _)| *p)
+            .collect();
+
+        for position in positions {
+            let sprite = map.sprites.remove(position).unwrap();
+            let sprite = match self.stat {
+                Stat::Attack => sprite.with_attack(value),
+                Stat::Health => sprite.with_health(value),
+            };
+            map.sprites.place(position, sprite);
+        }
+        map
+    }
+
+    fn trial(&self, value: Health) -> Trial {
+        let mut game = Game::new(self.map(value)).with_protected(self.species);
+        match game.run(|_, _| Ok(())) {
+            Ok(GameOutcome::Complete(stats)) => {
+                if stats.victors == self.species {
+                    Trial::Flawless(stats)
+                } else {
+                    Trial::Defeat(stats)
+                }
+            }
+            Ok(GameOutcome::Stopped) => Trial::Casualty(game.rounds() + 1),
+            Err(_) => Trial::Stalled,
+        }
+    }
+
+    pub fn run(&self) -> Search {
+        let mut search = Search {
+            trials: BTreeMap::new(),
+        };
+        let batch = rayon::current_num_threads().max(1);
+        let mut values = self.range.clone().peekable();
+
+        while values.peek().is_some() && search.minimum().is_none() {
+            let chunk: Vec<Health> = values.by_ref().take(batch).collect();
+            search.trials.extend(
+                chunk
+                    .into_par_iter()
+                    .map(|value| (value, self.trial(value)))
+                    .collect::<Vec<_>>(),
+            );
+        }
+        search
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::map::MapBuilder;
+
+    fn example() -> Map {
+        MapBuilder::default()
+            .build(include_str!("../examples/combat/initial.txt"))
+            .unwrap()
+    }
+
+    #[test]
+    fn attack() {
+        let search = FlawlessSearch::new(example(), Species::Elf)
+            .with_range(4..=200)
+            .run();
+
+        let (attack, stats) = search.minimum().unwrap();
+        assert_eq!(attack, 15);
+        assert_eq!(stats.rounds, 29);
+        assert_eq!(stats.score, 4988);
+
+        let tried: Vec<_> = search.trials().map(|(v, _)| v).take(12).collect();
+        assert_eq!(tried, (4..=15).collect::<Vec<_>>());
+        for (value, trial) in search.trials().take_while(|(v, _)| *v < 15) {
+            match trial {
+                Trial::Casualty(_) => {}
+                other => panic!("Expected a casualty at {}, got {:?}", value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn health() {
+        let search = FlawlessSearch::new(example(), Species::Goblin).with_stat(Stat::Health);
+
+        let result = search.run();
+        let (health, stats) = result.minimum().unwrap();
+        assert_eq!(health, 142);
+        assert_eq!(stats.rounds, 47);
+        assert_eq!(stats.score, 16826);
+
+        let weaker = search.with_range(100..=141).run();
+        assert!(weaker.minimum().is_none());
+        assert_eq!(weaker.trials().count(), 42);
+    }
+}
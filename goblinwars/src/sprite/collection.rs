@@ -33,6 +33,10 @@ impl Sprites {
         self.sprites.insert(position, sprite);
     }
 
+    pub fn remove(&mut self, position: Point) -> Option<Sprite> {
+        self.sprites.remove(&position)
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&Point, &Sprite)> {
         self.sprites.iter()
     }
@@ -0,0 +1,532 @@
+//! Discover which numeric opcode corresponds to which operation,
+//! given samples of registers before and after an instruction ran.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use failure::Fail;
+use itertools::Itertools;
+
+use super::{
+    Opcode, ParseRawInstructionError, ParseRegisterError, RawInstruction, Register, Value,
+};
+
+/// An observation of a single instruction being processed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Sample {
+    before: Register,
+    instruction: RawInstruction,
+    after: Register,
+}
+
+impl Sample {
+    #[cfg(test)]
+    pub(crate) fn new(before: Register, instruction: RawInstruction, after: Register) -> Self {
+        Self {
+            before,
+            instruction,
+            after,
+        }
+    }
+
+    /// Would this sample be produced if the instruction were this opcode?
+    pub(crate) fn evaluate(&self, opcode: Opcode) -> bool {
+        let instruction = self.instruction.convert(opcode);
+
+        let mut register = self.before.clone();
+        match instruction.process(&mut register) {
+            Ok(()) => register == self.after,
+            Err(_) => false,
+        }
+    }
+
+    /// All opcodes consistent with this sample.
+    pub(crate) fn identify(&self) -> BTreeSet<Opcode> {
+        self.candidates(&Opcode::all())
+    }
+
+    fn candidates(&self, opcodes: &[Opcode]) -> BTreeSet<Opcode> {
+        opcodes
+            .iter()
+            .cloned()
+            .filter(|oc| self.evaluate(*oc))
+            .collect()
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Before: {}", self.before)?;
+        writeln!(f, "{}", self.instruction)?;
+        writeln!(f, "After: {}", self.after)
+    }
+}
+
+#[derive(Debug, Fail)]
+pub(crate) enum ParseSampleError {
+    #[fail(display = "Register Error: {}", _0)]
+    Register(ParseRegisterError),
+
+    #[fail(display = "No before sample found: {}", _0)]
+    MissingBefore(String),
+
+    #[fail(display = "No instruction found: {}", _0)]
+    MissingInstruction(String),
+
+    #[fail(display = "Instruction Error: {}", _0)]
+    Instruction(ParseRawInstructionError),
+
+    #[fail(display = "No after sample found: {}", _0)]
+    MissingAfter(String),
+}
+
+impl From<ParseRegisterError> for ParseSampleError {
+    fn from(error: ParseRegisterError) -> Self {
+        ParseSampleError::Register(error)
+    }
+}
+
+impl From<ParseRawInstructionError> for ParseSampleError {
+    fn from(error: ParseRawInstructionError) -> Self {
+        ParseSampleError::Instruction(error)
+    }
+}
+
+impl FromStr for Sample {
+    type Err = ParseSampleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        let bline = lines
+            .next()
+            .ok_or_else(|| ParseSampleError::MissingBefore(s.to_string()))?;
+
+        if !bline.starts_with("Before: ") {
+            return Err(ParseSampleError::MissingBefore(bline.to_string()));
+        }
+
+        let before: Register = bline
+            .split(':')
+            .nth(1)
+            .ok_or_else(|| ParseSampleError::MissingBefore(bline.to_string()))?
+            .parse()?;
+
+        let instruction: RawInstruction = lines
+            .next()
+            .ok_or_else(|| ParseSampleError::MissingInstruction(s.to_string()))?
+            .parse()?;
+
+        let aline = lines
+            .next()
+            .ok_or_else(|| ParseSampleError::MissingAfter(s.to_string()))?;
+
+        if !aline.starts_with("After: ") {
+            return Err(ParseSampleError::MissingAfter(aline.to_string()));
+        }
+
+        let after: Register = aline
+            .split(':')
+            .nth(1)
+            .ok_or_else(|| ParseSampleError::MissingAfter(aline.to_string()))?
+            .parse()?;
+
+        Ok(Sample {
+            before,
+            instruction,
+            after,
+        })
+    }
+}
+
+/// How many samples for each opcode number agreed with each operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Consistency {
+    samples: BTreeMap<Value, usize>,
+    consistent: BTreeMap<Value, usize>,
+    matches: BTreeMap<Value, BTreeMap<Opcode, usize>>,
+}
+
+impl Consistency {
+    /// The number of samples observed for an opcode number.
+    pub(crate) fn samples(&self, code: Value) -> usize {
+        self.samples.get(&code).cloned().unwrap_or(0)
+    }
+
+    /// The number of samples for an opcode number which were
+    /// consistent with the given operation.
+    pub(crate) fn matches(&self, code: Value, opcode: Opcode) -> usize {
+        self.matches
+            .get(&code)
+            .and_then(|m| m.get(&opcode))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// The number of samples for an opcode number which were
+    /// consistent with at least one operation.
+    pub(crate) fn consistent(&self, code: Value) -> usize {
+        self.consistent.get(&code).cloned().unwrap_or(0)
+    }
+
+    fn record(&mut self, code: Value, candidates: &BTreeSet<Opcode>) {
+        *self.samples.entry(code).or_insert(0) += 1;
+        if !candidates.is_empty() {
+            *self.consistent.entry(code).or_insert(0) += 1;
+        }
+        let matches = self.matches.entry(code).or_default();
+        for oc in candidates {
+            *matches.entry(*oc).or_insert(0) += 1;
+        }
+    }
+}
+
+/// Why a sample couldn't be reconciled with the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Contradiction {
+    /// No operation produces this sample.
+    NoMatch(Sample),
+
+    /// The sample doesn't agree with the earlier samples for the same opcode number.
+    Inconsistent(Sample),
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Contradiction::NoMatch(s) => write!(f, "no operation matches:\n{}", s),
+            Contradiction::Inconsistent(s) => write!(f, "inconsistent with other samples:\n{}", s),
+        }
+    }
+}
+
+/// A complete decoding of opcode numbers into operations.
+#[derive(Debug, Clone)]
+pub(crate) struct Solution {
+    codes: BTreeMap<Value, Opcode>,
+}
+
+impl Solution {
+    pub(crate) fn codes(&self) -> &BTreeMap<Value, Opcode> {
+        &self.codes
+    }
+}
+
+/// The diagnosis when samples can't be decoded into a unique mapping.
+#[derive(Debug, Clone, Fail)]
+pub(crate) struct Unsolved {
+    candidates: BTreeMap<Value, BTreeSet<Opcode>>,
+    contradictions: Vec<Contradiction>,
+    solutions: usize,
+    consistency: Consistency,
+}
+
+impl Unsolved {
+    /// The opcode numbers which are not pinned to a single operation,
+    /// and the operations they could still be.
+    pub(crate) fn ambiguous(&self) -> BTreeMap<Value, BTreeSet<Opcode>> {
+        self.candidates
+            .iter()
+            .filter(|(_, c)| c.len() != 1)
+            .map(|(k, c)| (*k, c.clone()))
+            .collect()
+    }
+
+    /// Samples which were discarded because they contradict the rest.
+    pub(crate) fn contradictions(&self) -> &[Contradiction] {
+        &self.contradictions
+    }
+
+    /// The number of complete mappings consistent with the samples,
+    /// either zero or more than one.
+    pub(crate) fn solutions(&self) -> usize {
+        self.solutions
+    }
+
+    #[cfg(test)]
+    pub(crate) fn consistency(&self) -> &Consistency {
+        &self.consistency
+    }
+}
+
+impl fmt::Display for Unsolved {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.solutions == 0 {
+            write!(f, "No decoding is consistent with the samples")?;
+        } else {
+            write!(f, "Samples allow {} decodings", self.solutions)?;
+        }
+        for (code, candidates) in &self.ambiguous() {
+            write!(
+                f,
+                "\n  {} could be: {} ({} of {} samples matched any operation)",
+                code,
+                candidates
+                    .iter()
+                    .map(|c| format!("{:?} x{}", c, self.consistency.matches(*code, *c)))
+                    .join(", "),
+                self.consistency.consistent(*code),
+                self.consistency.samples(*code)
+            )?;
+        }
+        for contradiction in &self.contradictions {
+            write!(f, "\n  {}", contradiction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the operation behind each opcode number from samples.
+#[derive(Debug, Clone)]
+pub(crate) struct Solver {
+    opcodes: Vec<Opcode>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    /// A solver over the full set of operations.
+    pub(crate) fn new() -> Self {
+        Self::with_opcodes(&Opcode::all())
+    }
+
+    /// A solver where opcode numbers can only be one of the given operations.
+    pub(crate) fn with_opcodes(opcodes: &[Opcode]) -> Self {
+        Self {
+            opcodes: opcodes.to_vec(),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn solve(&self, samples: &[Sample]) -> Result<Solution, Unsolved> {
+        let mut candidates: BTreeMap<Value, BTreeSet<Opcode>> = BTreeMap::new();
+        let mut contradictions = Vec::new();
+        let mut consistency = Consistency::default();
+
+        for sample in samples {
+            let code = sample.instruction.opcode();
+            let sample_candidates = sample.candidates(&self.opcodes);
+            consistency.record(code, &sample_candidates);
+
+            if sample_candidates.is_empty() {
+                contradictions.push(Contradiction::NoMatch(sample.clone()));
+                continue;
+            }
+
+            let current = candidates
+                .entry(code)
+                .or_insert_with(|| sample_candidates.clone());
+
+            let narrowed: BTreeSet<Opcode> =
+                current.intersection(&sample_candidates).cloned().collect();
+            if narrowed.is_empty() {
+                contradictions.push(Contradiction::Inconsistent(sample.clone()));
+            } else {
+                *current = narrowed;
+            }
+        }
+
+        eliminate(&mut candidates);
+
+        let mut solutions = Vec::new();
+        search(&candidates, &mut solutions);
+
+        if solutions.len() == 1 && contradictions.is_empty() {
+            Ok(Solution {
+                codes: solutions.pop().unwrap(),
+            })
+        } else {
+            Err(Unsolved {
+                candidates,
+                contradictions,
+                solutions: solutions.len(),
+                consistency,
+            })
+        }
+    }
+}
+
+/// Repeatedly remove operations which are already claimed by an opcode
+/// number with a single candidate.
+fn eliminate(candidates: &mut BTreeMap<Value, BTreeSet<Opcode>>) {
+    let mut fixed = BTreeSet::new();
+
+    loop {
+        let next = candidates
+            .iter()
+            .filter(|(_, c)| c.len() == 1)
+            .map(|(k, c)| (*k, *c.iter().next().unwrap()))
+            .find(|(k, _)| !fixed.contains(k));
+
+        let (code, opcode) = match next {
+            Some(n) => n,
+            None => break,
+        };
+        fixed.insert(code);
+
+        for (k, c) in candidates.iter_mut() {
+            if *k != code && c.len() > 1 {
+                c.remove(&opcode);
+            }
+        }
+    }
+}
+
+/// Whether every opcode number can be given a distinct operation from
+/// its candidates, found by growing a matching along augmenting paths.
+fn assignable(candidates: &BTreeMap<Value, BTreeSet<Opcode>>) -> bool {
+    fn augment(
+        code: Value,
+        candidates: &BTreeMap<Value, BTreeSet<Opcode>>,
+        visited: &mut BTreeSet<Opcode>,
+        owners: &mut BTreeMap<Opcode, Value>,
+    ) -> bool {
+        for opcode in &candidates[&code] {
+            if !visited.insert(*opcode) {
+                continue;
+            }
+            let free = match owners.get(opcode) {
+                Some(&owner) => augment(owner, candidates, visited, owners),
+                None => true,
+            };
+            if free {
+                owners.insert(*opcode, code);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut owners = BTreeMap::new();
+    candidates
+        .keys()
+        .all(|code| augment(*code, candidates, &mut BTreeSet::new(), &mut owners))
+}
+
+/// Enumerate complete assignments of opcode numbers to distinct operations,
+/// stopping once more than one is found.
+///
+/// Each choice is propagated with `eliminate`, and branches which can no
+/// longer be assigned one-to-one are abandoned, so every branch explored
+/// leads to at least one solution.
+fn search(
+    candidates: &BTreeMap<Value, BTreeSet<Opcode>>,
+    solutions: &mut Vec<BTreeMap<Value, Opcode>>,
+) {
+    if solutions.len() > 1 || !assignable(candidates) {
+        return;
+    }
+
+    let open = candidates
+        .iter()
+        .filter(|(_, c)| c.len() > 1)
+        .min_by_key(|(_, c)| c.len());
+
+    let (code, options) = match open {
+        Some(item) => item,
+        None => {
+            solutions.push(
+                candidates
+                    .iter()
+                    .map(|(code, c)| (*code, *c.iter().next().unwrap()))
+                    .collect(),
+            );
+            return;
+        }
+    };
+
+    for opcode in options {
+        let mut narrowed = candidates.clone();
+        narrowed.insert(*code, Some(*opcode).into_iter().collect());
+        eliminate(&mut narrowed);
+        search(&narrowed, solutions);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(before: &[Value], instruction: RawInstruction, opcode: Opcode) -> Sample {
+        let before = Register::from(before.to_vec());
+        let mut after = before.clone();
+        instruction.convert(opcode).process(&mut after).unwrap();
+        Sample::new(before, instruction, after)
+    }
+
+    #[test]
+    fn solve_subset() {
+        let solver = Solver::with_opcodes(&[Opcode::Addr, Opcode::Mulr, Opcode::Seti]);
+        let samples = vec![
+            sample(&[3, 4, 0, 0], RawInstruction::new(0, 0, 1, 2), Opcode::Mulr),
+            sample(&[3, 4, 0, 0], RawInstruction::new(1, 0, 1, 2), Opcode::Addr),
+            sample(&[3, 4, 0, 0], RawInstruction::new(2, 7, 1, 2), Opcode::Seti),
+        ];
+
+        let solution = solver.solve(&samples).unwrap();
+        assert_eq!(solution.codes()[&0], Opcode::Mulr);
+        assert_eq!(solution.codes()[&1], Opcode::Addr);
+        assert_eq!(solution.codes()[&2], Opcode::Seti);
+    }
+
+    #[test]
+    fn ambiguous() {
+        let solver = Solver::with_opcodes(&[Opcode::Addr, Opcode::Mulr]);
+
+        // 2 + 2 == 2 * 2, so this sample can't tell the two apart.
+        let samples = vec![
+            sample(&[2, 2, 0, 0], RawInstruction::new(0, 0, 1, 2), Opcode::Addr),
+            sample(&[2, 2, 0, 0], RawInstruction::new(1, 0, 1, 2), Opcode::Mulr),
+        ];
+
+        let unsolved = solver.solve(&samples).unwrap_err();
+        assert_eq!(unsolved.solutions(), 2);
+        assert_eq!(unsolved.ambiguous().len(), 2);
+        assert!(unsolved.contradictions().is_empty());
+        assert_eq!(unsolved.consistency().matches(1, Opcode::Addr), 1);
+        assert_eq!(
+            unsolved.to_string(),
+            "Samples allow 2 decodings\n  \
+             0 could be: Addr x1, Mulr x1 (1 of 1 samples matched any operation)\n  \
+             1 could be: Addr x1, Mulr x1 (1 of 1 samples matched any operation)"
+        );
+    }
+
+    #[test]
+    fn contradiction() {
+        let solver = Solver::with_opcodes(&[Opcode::Addr, Opcode::Mulr]);
+        let samples = vec![
+            sample(&[3, 4, 0, 0], RawInstruction::new(0, 0, 1, 2), Opcode::Addr),
+            sample(&[3, 4, 0, 0], RawInstruction::new(0, 0, 1, 2), Opcode::Mulr),
+            sample(&[3, 4, 0, 0], RawInstruction::new(1, 0, 1, 2), Opcode::Mulr),
+        ];
+
+        let unsolved = solver.solve(&samples).unwrap_err();
+        assert_eq!(
+            unsolved.contradictions(),
+            &[Contradiction::Inconsistent(samples[1].clone())]
+        );
+        assert_eq!(unsolved.consistency().samples(0), 2);
+    }
+
+    #[test]
+    fn unassignable() {
+        // Three numbers share two operations, and the rest could be anything,
+        // so no assignment exists however the wide sets are chosen.
+        let mut candidates: BTreeMap<Value, BTreeSet<Opcode>> = (0..16)
+            .map(|code| (code, Opcode::all().into_iter().collect()))
+            .collect();
+        for code in 0..3 {
+            candidates.insert(code, [Opcode::Addr, Opcode::Addi].iter().cloned().collect());
+        }
+        assert!(!assignable(&candidates));
+
+        let mut solutions = Vec::new();
+        search(&candidates, &mut solutions);
+        assert!(solutions.is_empty());
+    }
+}
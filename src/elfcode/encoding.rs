@@ -0,0 +1,445 @@
+//! Encodings of elfcode programs other than source text.
+//!
+//! The binary encoding starts with a magic number and version, then a
+//! flag byte for which directives follow: the instruction pointer register,
+//! the number of registers, and the initial register values (as a count and
+//! then the values). Next is the number of instructions, then for each
+//! instruction the opcode as a single byte followed by its three values.
+//! All numbers other than the opcode and flags are zigzag LEB128 varints,
+//! so small values take a single byte.
+
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+
+use failure::Fail;
+
+use super::program::Program;
+use super::{Instruction, Opcode, RawInstruction, Value};
+
+const MAGIC: &[u8] = b"ELF";
+const VERSION: u8 = 1;
+
+const FLAG_IP: u8 = 1;
+const FLAG_REGISTERS: u8 = 1 << 1;
+const FLAG_INITIAL: u8 = 1 << 2;
+
+#[derive(Debug, Fail, PartialEq)]
+pub(crate) enum DecodeError {
+    #[fail(display = "Not an elfcode binary")]
+    InvalidMagic,
+
+    #[fail(display = "Unsupported version: {}", _0)]
+    UnsupportedVersion(u8),
+
+    #[fail(display = "Unexpected end of input")]
+    UnexpectedEnd,
+
+    #[fail(display = "Invalid opcode: {}", _0)]
+    InvalidOpcode(u8),
+
+    #[fail(display = "Value too large")]
+    Overflow,
+
+    #[fail(display = "{} trailing bytes", _0)]
+    TrailingBytes(usize),
+
+    #[fail(display = "Unknown flags: {:#x}", _0)]
+    UnknownFlags(u8),
+
+    #[fail(display = "Invalid length: {}", _0)]
+    InvalidLength(Value),
+
+    #[fail(display = "{} initial values given for {} registers", _1, _0)]
+    MismatchedRegisters(usize, usize),
+}
+
+fn write_varint(buffer: &mut Vec<u8>, value: Value) {
+    let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+    loop {
+        let byte = (zigzag & 0x7f) as u8;
+        zigzag >>= 7;
+        if zigzag == 0 {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    position: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let b = *self
+            .bytes
+            .get(self.position)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(b)
+    }
+
+    fn varint(&mut self) -> Result<Value, DecodeError> {
+        let mut zigzag: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 64 {
+                return Err(DecodeError::Overflow);
+            }
+            zigzag |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        Ok((zigzag >> 1) as Value ^ -((zigzag & 1) as Value))
+    }
+
+    fn length(&mut self) -> Result<usize, DecodeError> {
+        let length = self.varint()?;
+        if length < 0 {
+            return Err(DecodeError::InvalidLength(length));
+        }
+        Ok(length as usize)
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+}
+
+impl Program {
+    /// Encode this program in the compact binary format.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(MAGIC);
+        buffer.push(VERSION);
+
+        let mut flags = 0;
+        if self.ip().is_some() {
+            flags |= FLAG_IP;
+        }
+        if self.registers().is_some() {
+            flags |= FLAG_REGISTERS;
+        }
+        if self.initial().is_some() {
+            flags |= FLAG_INITIAL;
+        }
+        buffer.push(flags);
+
+        if let Some(ip) = self.ip() {
+            write_varint(&mut buffer, ip);
+        }
+        if let Some(registers) = self.registers() {
+            write_varint(&mut buffer, registers as Value);
+        }
+        if let Some(initial) = self.initial() {
+            write_varint(&mut buffer, initial.len() as Value);
+            for value in initial {
+                write_varint(&mut buffer, *value);
+            }
+        }
+
+        write_varint(&mut buffer, self.len() as Value);
+        let opcodes = Opcode::all();
+        for instruction in self.instructions() {
+            let index = opcodes
+                .iter()
+                .position(|oc| *oc == instruction.opcode)
+                .expect("Every opcode is listed");
+            buffer.push(index as u8);
+            write_varint(&mut buffer, instruction.input_a);
+            write_varint(&mut buffer, instruction.input_b);
+            write_varint(&mut buffer, instruction.output);
+        }
+        buffer
+    }
+
+    /// Decode a program from the compact binary format.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(DecodeError::InvalidMagic);
+        }
+
+        let mut reader = Reader {
+            bytes,
+            position: MAGIC.len(),
+        };
+
+        let version = reader.byte()?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let flags = reader.byte()?;
+        if flags & !(FLAG_IP | FLAG_REGISTERS | FLAG_INITIAL) != 0 {
+            return Err(DecodeError::UnknownFlags(flags));
+        }
+
+        let ip = match flags & FLAG_IP {
+            0 => None,
+            _ => Some(reader.varint()?),
+        };
+        let registers = match flags & FLAG_REGISTERS {
+            0 => None,
+            _ => Some(reader.length()?),
+        };
+        let initial = match flags & FLAG_INITIAL {
+            0 => None,
+            _ => {
+                let length = reader.length()?;
+                Some(
+                    (0..length)
+                        .map(|_| reader.varint())
+                        .collect::<Result<Vec<_>, _>>()?,
+                )
+            }
+        };
+
+        let length = reader.length()?;
+        let opcodes = Opcode::all();
+        let mut instructions = Vec::new();
+        for _ in 0..length {
+            let index = reader.byte()?;
+            let opcode = *opcodes
+                .get(index as usize)
+                .ok_or(DecodeError::InvalidOpcode(index))?;
+            let a = reader.varint()?;
+            let b = reader.varint()?;
+            let c = reader.varint()?;
+            instructions.push(Instruction::new(opcode, a, b, c));
+        }
+
+        if reader.remaining() > 0 {
+            return Err(DecodeError::TrailingBytes(reader.remaining()));
+        }
+
+        if let (Some(registers), Some(initial)) = (registers, &initial) {
+            if registers != initial.len() {
+                return Err(DecodeError::MismatchedRegisters(registers, initial.len()));
+            }
+        }
+
+        let mut program = Program::new(instructions, ip);
+        if let Some(registers) = registers {
+            program = program.with_registers(registers);
+        }
+        if let Some(initial) = initial {
+            program = program.with_initial(initial);
+        }
+        Ok(program)
+    }
+}
+
+#[derive(Debug, Fail, PartialEq)]
+pub(crate) enum TableError {
+    #[fail(display = "No number for opcode: {:?}", _0)]
+    UnknownOpcode(Opcode),
+
+    #[fail(display = "No opcode for number: {}", _0)]
+    UnknownNumber(Value),
+}
+
+/// A mapping between opcode numbers and operations, used to convert
+/// between instructions and numeric instructions like those in day 16.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct OpcodeTable {
+    codes: BTreeMap<Value, Opcode>,
+}
+
+impl From<BTreeMap<Value, Opcode>> for OpcodeTable {
+    fn from(codes: BTreeMap<Value, Opcode>) -> Self {
+        Self { codes }
+    }
+}
+
+impl FromIterator<(Value, Opcode)> for OpcodeTable {
+    fn from_iter<I: IntoIterator<Item = (Value, Opcode)>>(iter: I) -> Self {
+        Self {
+            codes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl OpcodeTable {
+    /// Numbers each opcode in the order of `Opcode::all`.
+    pub(crate) fn standard() -> Self {
+        Opcode::all()
+            .into_iter()
+            .enumerate()
+            .map(|(i, oc)| (i as Value, oc))
+            .collect()
+    }
+
+    pub(crate) fn insert(&mut self, code: Value, opcode: Opcode) {
+        self.codes.insert(code, opcode);
+    }
+
+    pub(crate) fn opcode(&self, code: Value) -> Option<Opcode> {
+        self.codes.get(&code).cloned()
+    }
+
+    pub(crate) fn code(&self, opcode: Opcode) -> Option<Value> {
+        self.codes
+            .iter()
+            .find(|(_, oc)| **oc == opcode)
+            .map(|(code, _)| *code)
+    }
+
+    pub(crate) fn encode(&self, instruction: &Instruction) -> Result<RawInstruction, TableError> {
+        let code = self
+            .code(instruction.opcode)
+            .ok_or(TableError::UnknownOpcode(instruction.opcode))?;
+        Ok(RawInstruction::new(
+            code,
+            instruction.input_a,
+            instruction.input_b,
+            instruction.output,
+        ))
+    }
+
+    pub(crate) fn decode(&self, instruction: &RawInstruction) -> Result<Instruction, TableError> {
+        let opcode = self
+            .opcode(instruction.opcode())
+            .ok_or_else(|| TableError::UnknownNumber(instruction.opcode()))?;
+        Ok(instruction.convert(opcode))
+    }
+
+    /// Encode a program's instructions as numeric instructions.
+    pub(crate) fn encode_program(
+        &self,
+        program: &Program,
+    ) -> Result<Vec<RawInstruction>, TableError> {
+        program
+            .instructions()
+            .iter()
+            .map(|i| self.encode(i))
+            .collect()
+    }
+
+    /// Decode numeric instructions into a program.
+    pub(crate) fn decode_program(
+        &self,
+        instructions: &[RawInstruction],
+        ip: Option<Value>,
+    ) -> Result<Program, TableError> {
+        let instructions = instructions
+            .iter()
+            .map(|i| self.decode(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Program::new(instructions, ip))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Program {
+        "#ip 5
+seti 123 0 4
+bani 4 456 4
+eqri 4 72 4
+addr 4 5 5
+seti 0 0 5
+bori 4 65536 3
+seti 14464005 5 4
+muli 4 -65899 4"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn varints() {
+        for value in &[0, 1, -1, 63, -64, 64, 300, Value::MAX, Value::MIN] {
+            let mut buffer = Vec::new();
+            write_varint(&mut buffer, *value);
+            let mut reader = Reader {
+                bytes: &buffer,
+                position: 0,
+            };
+            assert_eq!(reader.varint().unwrap(), *value);
+            assert_eq!(reader.remaining(), 0);
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let program = example();
+        let bytes = program.to_bytes();
+        assert_eq!(Program::from_bytes(&bytes).unwrap(), program);
+
+        let program = Program::from(program.instructions().to_vec());
+        assert_eq!(Program::from_bytes(&program.to_bytes()).unwrap(), program);
+
+        let program = example()
+            .with_registers(6)
+            .with_initial(vec![1, 0, 0, 0, 0, -1]);
+        assert_eq!(Program::from_bytes(&program.to_bytes()).unwrap(), program);
+    }
+
+    #[test]
+    fn binary_errors() {
+        let bytes = example().to_bytes();
+        assert_eq!(
+            Program::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Program::from_bytes(b"ELE\x01"),
+            Err(DecodeError::InvalidMagic)
+        );
+
+        assert_eq!(
+            Program::from_bytes(b"ELF\x01\x08"),
+            Err(DecodeError::UnknownFlags(8))
+        );
+
+        assert_eq!(
+            Program::from_bytes(b"ELF\x01\x06\x04\x02\x02\x00"),
+            Err(DecodeError::MismatchedRegisters(2, 1))
+        );
+
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert_eq!(
+            Program::from_bytes(&extended),
+            Err(DecodeError::TrailingBytes(1))
+        );
+    }
+
+    #[test]
+    fn table_round_trip() {
+        let table: OpcodeTable = Opcode::all()
+            .into_iter()
+            .rev()
+            .enumerate()
+            .map(|(i, oc)| (i as Value, oc))
+            .collect();
+
+        let program = example();
+        let raw = table.encode_program(&program).unwrap();
+        assert_eq!(raw[0], RawInstruction::new(6, 123, 0, 4));
+        assert_eq!(table.decode_program(&raw, program.ip()).unwrap(), program);
+    }
+
+    #[test]
+    fn table_errors() {
+        let mut table = OpcodeTable::default();
+        table.insert(3, Opcode::Seti);
+
+        let instruction = Instruction::new(Opcode::Addi, 1, 2, 3);
+        assert_eq!(
+            table.encode(&instruction),
+            Err(TableError::UnknownOpcode(Opcode::Addi))
+        );
+        assert_eq!(
+            table.decode(&RawInstruction::new(4, 1, 2, 3)),
+            Err(TableError::UnknownNumber(4))
+        );
+        assert_eq!(OpcodeTable::standard().code(Opcode::Addi), Some(1));
+    }
+}
@@ -0,0 +1,222 @@
+//! Generate random programs and opcode samples in the day 16 format,
+//! so that opcode discovery can be checked against a known answer.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use failure::Fail;
+
+use super::discovery::Sample;
+use super::encoding::{OpcodeTable, TableError};
+use super::{Instruction, Opcode, RawInstruction, Register, RegisterError, Value};
+
+#[derive(Debug, Fail)]
+pub(crate) enum GenerateError {
+    #[fail(display = "Program failed: {}", _0)]
+    Register(#[cause] RegisterError),
+
+    #[fail(display = "Permutation is incomplete: {}", _0)]
+    Table(#[cause] TableError),
+}
+
+impl From<RegisterError> for GenerateError {
+    fn from(error: RegisterError) -> Self {
+        GenerateError::Register(error)
+    }
+}
+
+impl From<TableError> for GenerateError {
+    fn from(error: TableError) -> Self {
+        GenerateError::Table(error)
+    }
+}
+
+/// A small SplitMix64 generator, so that cases are reproducible from a seed.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// A generated puzzle, with the answers the decoder should find.
+#[derive(Debug, Clone)]
+pub(crate) struct Generated {
+    codes: BTreeMap<Value, Opcode>,
+    samples: Vec<Sample>,
+    program: Vec<RawInstruction>,
+    result: Register,
+}
+
+impl Generated {
+    /// The hidden mapping from opcode numbers to operations.
+    pub(crate) fn codes(&self) -> &BTreeMap<Value, Opcode> {
+        &self.codes
+    }
+
+    pub(crate) fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// The program, encoded with the hidden opcode numbers.
+    pub(crate) fn program(&self) -> &[RawInstruction] {
+        &self.program
+    }
+
+    /// The registers after running the program from all zeros.
+    pub(crate) fn result(&self) -> &Register {
+        &self.result
+    }
+}
+
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for sample in &self.samples {
+            writeln!(f, "{}", sample)?;
+        }
+        writeln!(f)?;
+        writeln!(f)?;
+        for instruction in &self.program {
+            writeln!(f, "{}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds random samples and programs for a hidden opcode permutation.
+#[derive(Debug, Clone)]
+pub(crate) struct Generator {
+    rng: Rng,
+    registers: usize,
+}
+
+impl Generator {
+    /// A generator for four registers, like the day 16 puzzle.
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng(seed),
+            registers: 4,
+        }
+    }
+
+    fn value(&mut self) -> Value {
+        self.rng.below(self.registers) as Value
+    }
+
+    /// A random assignment of opcode numbers to operations.
+    pub(crate) fn permutation(&mut self) -> BTreeMap<Value, Opcode> {
+        let mut opcodes = Opcode::all();
+        for i in (1..opcodes.len()).rev() {
+            let j = self.rng.below(i + 1);
+            opcodes.swap(i, j);
+        }
+        opcodes
+            .into_iter()
+            .enumerate()
+            .map(|(i, oc)| (i as Value, oc))
+            .collect()
+    }
+
+    /// A random instruction, whose inputs are all valid registers.
+    pub(crate) fn instruction(&mut self, opcode: Opcode) -> Instruction {
+        Instruction::new(opcode, self.value(), self.value(), self.value())
+    }
+
+    /// A random straight-line program.
+    pub(crate) fn program(&mut self, length: usize) -> Vec<Instruction> {
+        let opcodes = Opcode::all();
+        (0..length)
+            .map(|_| {
+                let oc = opcodes[self.rng.below(opcodes.len())];
+                self.instruction(oc)
+            })
+            .collect()
+    }
+
+    /// A sample of a random instruction with the given opcode.
+    pub(crate) fn sample(&mut self, code: Value, opcode: Opcode) -> Sample {
+        let before = Register::from(
+            (0..self.registers)
+                .map(|_| self.value())
+                .collect::<Vec<_>>(),
+        );
+        let instruction = self.instruction(opcode);
+        let mut after = before.clone();
+        instruction
+            .process(&mut after)
+            .expect("Generated instructions use valid registers");
+        Sample::new(
+            before,
+            RawInstruction::new(
+                code,
+                instruction.input_a,
+                instruction.input_b,
+                instruction.output,
+            ),
+            after,
+        )
+    }
+
+    /// Generate a puzzle with a hidden permutation, `samples` observations
+    /// for each opcode number, and a program of the given length.
+    ///
+    /// Programs which overflow are discarded, and a fresh one is drawn.
+    pub(crate) fn generate(
+        &mut self,
+        samples: usize,
+        length: usize,
+    ) -> Result<Generated, GenerateError> {
+        let codes = self.permutation();
+        loop {
+            let program = self.program(length);
+            match self.generate_from(codes.clone(), &program, samples) {
+                Err(GenerateError::Register(RegisterError::ArithmeticOverflow)) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Generate a puzzle for a chosen permutation and program, with
+    /// `samples` random observations for each opcode number.
+    pub(crate) fn generate_from(
+        &mut self,
+        codes: BTreeMap<Value, Opcode>,
+        program: &[Instruction],
+        samples: usize,
+    ) -> Result<Generated, GenerateError> {
+        let mut observations = Vec::with_capacity(codes.len() * samples);
+        for _ in 0..samples {
+            for (code, opcode) in &codes {
+                observations.push(self.sample(*code, *opcode));
+            }
+        }
+
+        let mut result = Register::new(self.registers);
+        for instruction in program {
+            instruction.process(&mut result)?;
+        }
+
+        let table = OpcodeTable::from(codes.clone());
+        let program = program
+            .iter()
+            .map(|i| table.encode(i))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Generated {
+            codes,
+            samples: observations,
+            program,
+            result,
+        })
+    }
+}
@@ -9,6 +9,12 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 // pub(crate) mod decompile;
+pub(crate) mod discovery;
+pub(crate) mod encoding;
+#[cfg(test)]
+pub(crate) mod generator;
+pub(crate) mod monitor;
+pub(crate) mod program;
 pub(crate) mod psuedocoder;
 
 pub(crate) type Value = i64;
@@ -20,6 +26,14 @@ pub(crate) enum RegisterError {
 
     #[fail(display = "Invalid Value")]
     InvalidValue,
+
+    #[fail(display = "Arithmetic overflow at instruction {}", _0)]
+    Overflow(usize),
+
+    /// An instruction overflowed outside of a program, so there's no
+    /// location to report.
+    #[fail(display = "Arithmetic overflow")]
+    ArithmeticOverflow,
 }
 
 impl From<TryFromIntError> for RegisterError {
@@ -49,7 +63,7 @@ impl Register {
     }
 
     pub(crate) fn store(&mut self, address: Value, value: Value) -> Result<(), RegisterError> {
-        if address < 0 || address > self.memory.len() as Value {
+        if address < 0 || address >= self.memory.len() as Value {
             return Err(RegisterError::InvalidAddress(address));
         }
 
@@ -58,7 +72,7 @@ impl Register {
     }
 
     pub(crate) fn get(&self, address: Value) -> Result<Value, RegisterError> {
-        if address < 0 || address > self.memory.len() as Value {
+        if address < 0 || address >= self.memory.len() as Value {
             return Err(RegisterError::InvalidAddress(address));
         }
         Ok(self.memory[address as usize])
@@ -75,6 +89,35 @@ impl fmt::Display for Register {
     }
 }
 
+#[derive(Debug, Fail)]
+pub(crate) enum ParseRegisterError {
+    #[fail(display = "Invalid Value: {}", _0)]
+    InvalidValue(ParseIntError),
+}
+
+impl From<ParseIntError> for ParseRegisterError {
+    fn from(error: ParseIntError) -> Self {
+        ParseRegisterError::InvalidValue(error)
+    }
+}
+
+impl FromStr for Register {
+    type Err = ParseRegisterError;
+
+    #[allow(deprecated)]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<Value> = s
+            .trim()
+            .trim_left_matches('[')
+            .trim_right_matches(']')
+            .split(',')
+            .map(|i| i.trim().parse::<Value>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::from(values))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(crate) enum Opcode {
     Addr,
@@ -117,6 +160,20 @@ impl Opcode {
         }
     }
 
+    /// How the two inputs of this opcode are interpreted.
+    pub(crate) fn operands(self) -> (Operand, Operand) {
+        use self::Operand::*;
+        match self {
+            Opcode::Addr | Opcode::Mulr | Opcode::Banr | Opcode::Borr => (Register, Register),
+            Opcode::Gtrr | Opcode::Eqrr => (Register, Register),
+            Opcode::Addi | Opcode::Muli | Opcode::Bani | Opcode::Bori => (Register, Immediate),
+            Opcode::Gtri | Opcode::Eqri => (Register, Immediate),
+            Opcode::Gtir | Opcode::Eqir => (Immediate, Register),
+            Opcode::Setr => (Register, Unused),
+            Opcode::Seti => (Immediate, Unused),
+        }
+    }
+
     pub(crate) fn all() -> Vec<Opcode> {
         let mut opcodes = Vec::new();
 
@@ -135,6 +192,14 @@ impl Opcode {
     }
 }
 
+/// How an instruction interprets one of its inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operand {
+    Register,
+    Immediate,
+    Unused,
+}
+
 #[derive(Debug, Fail)]
 pub(crate) enum ParseOpcodeError {
     #[fail(display = "Invalid Opcode: {}", _0)]
@@ -167,6 +232,64 @@ impl FromStr for Opcode {
     }
 }
 
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Opcode::Addr => "addr",
+            Opcode::Addi => "addi",
+            Opcode::Mulr => "mulr",
+            Opcode::Muli => "muli",
+            Opcode::Banr => "banr",
+            Opcode::Bani => "bani",
+            Opcode::Borr => "borr",
+            Opcode::Bori => "bori",
+            Opcode::Setr => "setr",
+            Opcode::Seti => "seti",
+            Opcode::Gtir => "gtir",
+            Opcode::Gtri => "gtri",
+            Opcode::Gtrr => "gtrr",
+            Opcode::Eqir => "eqir",
+            Opcode::Eqri => "eqri",
+            Opcode::Eqrr => "eqrr",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How arithmetic instructions behave when the result doesn't fit in a `Value`.
+///
+/// All modes give the same results in debug and release builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Arithmetic {
+    /// Overflow is reported as an error.
+    #[default]
+    Checked,
+
+    /// Overflow wraps around (two's complement).
+    Wrapping,
+
+    /// Overflow is clamped to the bounds of `Value`.
+    Saturating,
+}
+
+impl Arithmetic {
+    fn add(self, a: Value, b: Value) -> Option<Value> {
+        match self {
+            Arithmetic::Checked => a.checked_add(b),
+            Arithmetic::Wrapping => Some(a.wrapping_add(b)),
+            Arithmetic::Saturating => Some(a.saturating_add(b)),
+        }
+    }
+
+    fn mul(self, a: Value, b: Value) -> Option<Value> {
+        match self {
+            Arithmetic::Checked => a.checked_mul(b),
+            Arithmetic::Wrapping => Some(a.wrapping_mul(b)),
+            Arithmetic::Saturating => Some(a.saturating_mul(b)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct Instruction {
     opcode: Opcode,
@@ -175,19 +298,19 @@ pub(crate) struct Instruction {
     output: Value,
 }
 
-fn gt(a: Value, b: Value) -> Value {
+fn gt(a: Value, b: Value) -> Option<Value> {
     if a > b {
-        1
+        Some(1)
     } else {
-        0
+        Some(0)
     }
 }
 
-fn eq(a: Value, b: Value) -> Value {
+fn eq(a: Value, b: Value) -> Option<Value> {
     if a == b {
-        1
+        Some(1)
     } else {
-        0
+        Some(0)
     }
 }
 
@@ -201,58 +324,96 @@ impl Instruction {
         }
     }
 
+    pub(crate) fn opcode(&self) -> Opcode {
+        self.opcode
+    }
+
+    /// The registers read by this instruction.
+    pub(crate) fn reads(&self) -> Vec<Value> {
+        let (kind_a, kind_b) = self.opcode.operands();
+        let mut inputs = Vec::new();
+        if kind_a == Operand::Register {
+            inputs.push(self.input_a);
+        }
+        if kind_b == Operand::Register {
+            inputs.push(self.input_b);
+        }
+        inputs
+    }
+
+    /// The register written by this instruction.
+    pub(crate) fn writes(&self) -> Value {
+        self.output
+    }
+
     fn rr<F>(&self, register: &mut Register, f: F) -> Result<(), RegisterError>
     where
-        F: FnOnce(Value, Value) -> Value,
+        F: FnOnce(Value, Value) -> Option<Value>,
     {
         let a = register.get(self.input_a)?;
         let b = register.get(self.input_b)?;
-        register.store(self.output, f(a, b))?;
+        let value = f(a, b).ok_or(RegisterError::ArithmeticOverflow)?;
+        register.store(self.output, value)?;
         Ok(())
     }
 
     fn ri<F>(&self, register: &mut Register, f: F) -> Result<(), RegisterError>
     where
-        F: FnOnce(Value, Value) -> Value,
+        F: FnOnce(Value, Value) -> Option<Value>,
     {
         let a = register.get(self.input_a)?;
         let b = self.input_b;
-        register.store(self.output, f(a, b))?;
+        let value = f(a, b).ok_or(RegisterError::ArithmeticOverflow)?;
+        register.store(self.output, value)?;
         Ok(())
     }
 
     fn ii<F>(&self, register: &mut Register, f: F) -> Result<(), RegisterError>
     where
-        F: FnOnce(Value, Value) -> Value,
+        F: FnOnce(Value, Value) -> Option<Value>,
     {
         let a = self.input_a;
         let b = self.input_b;
-        register.store(self.output, f(a, b))?;
+        let value = f(a, b).ok_or(RegisterError::ArithmeticOverflow)?;
+        register.store(self.output, value)?;
         Ok(())
     }
 
     fn ir<F>(&self, register: &mut Register, f: F) -> Result<(), RegisterError>
     where
-        F: FnOnce(Value, Value) -> Value,
+        F: FnOnce(Value, Value) -> Option<Value>,
     {
         let a = self.input_a;
         let b = register.get(self.input_b)?;
-        register.store(self.output, f(a, b))?;
+        let value = f(a, b).ok_or(RegisterError::ArithmeticOverflow)?;
+        register.store(self.output, value)?;
         Ok(())
     }
 
+    /// Apply this instruction to a register using the default (checked)
+    /// arithmetic of a processor.
     pub(crate) fn process(&self, register: &mut Register) -> Result<(), RegisterError> {
+        self.process_with(register, Arithmetic::default())
+    }
+
+    /// Apply this instruction to a register, handling overflow with
+    /// the given arithmetic.
+    pub(crate) fn process_with(
+        &self,
+        register: &mut Register,
+        arithmetic: Arithmetic,
+    ) -> Result<(), RegisterError> {
         match self.opcode {
-            Opcode::Addr => self.rr(register, |a, b| a + b),
-            Opcode::Addi => self.ri(register, |a, b| a + b),
-            Opcode::Mulr => self.rr(register, |a, b| a * b),
-            Opcode::Muli => self.ri(register, |a, b| a * b),
-            Opcode::Banr => self.rr(register, |a, b| a & b),
-            Opcode::Bani => self.ri(register, |a, b| a & b),
-            Opcode::Borr => self.rr(register, |a, b| a | b),
-            Opcode::Bori => self.ri(register, |a, b| a | b),
-            Opcode::Setr => self.rr(register, |a, _| a),
-            Opcode::Seti => self.ii(register, |a, _| a),
+            Opcode::Addr => self.rr(register, |a, b| arithmetic.add(a, b)),
+            Opcode::Addi => self.ri(register, |a, b| arithmetic.add(a, b)),
+            Opcode::Mulr => self.rr(register, |a, b| arithmetic.mul(a, b)),
+            Opcode::Muli => self.ri(register, |a, b| arithmetic.mul(a, b)),
+            Opcode::Banr => self.rr(register, |a, b| Some(a & b)),
+            Opcode::Bani => self.ri(register, |a, b| Some(a & b)),
+            Opcode::Borr => self.rr(register, |a, b| Some(a | b)),
+            Opcode::Bori => self.ri(register, |a, b| Some(a | b)),
+            Opcode::Setr => self.rr(register, |a, _| Some(a)),
+            Opcode::Seti => self.ii(register, |a, _| Some(a)),
             Opcode::Gtir => self.ir(register, gt),
             Opcode::Gtri => self.ri(register, gt),
             Opcode::Gtrr => self.rr(register, gt),
@@ -270,6 +431,9 @@ pub(crate) enum ParseInstructionError {
 
     #[fail(display = "Invalid Register")]
     InvalidRegister(ParseIntError),
+
+    #[fail(display = "Expected an opcode and 3 values, found {} fields", _0)]
+    WrongLength(usize),
 }
 
 impl From<ParseOpcodeError> for ParseInstructionError {
@@ -289,6 +453,9 @@ impl FromStr for Instruction {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let values: Vec<&str> = s.trim().split_whitespace().collect();
+        if values.len() != 4 {
+            return Err(ParseInstructionError::WrongLength(values.len()));
+        }
 
         let opcode: Opcode = values[0].parse()?;
 
@@ -311,6 +478,103 @@ impl FromStr for Instruction {
     }
 }
 
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.opcode, self.input_a, self.input_b, self.output
+        )
+    }
+}
+
+/// An instruction with a numeric opcode, which must be decoded
+/// before it can be run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RawInstruction {
+    opcode: Value,
+    input_a: Value,
+    input_b: Value,
+    output: Value,
+}
+
+impl RawInstruction {
+    pub(crate) fn new(opcode: Value, input_a: Value, input_b: Value, output: Value) -> Self {
+        Self {
+            opcode,
+            input_a,
+            input_b,
+            output,
+        }
+    }
+
+    pub(crate) fn opcode(&self) -> Value {
+        self.opcode
+    }
+
+    pub(crate) fn convert(&self, opcode: Opcode) -> Instruction {
+        Instruction::new(opcode, self.input_a, self.input_b, self.output)
+    }
+}
+
+impl fmt::Display for RawInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.opcode, self.input_a, self.input_b, self.output
+        )
+    }
+}
+
+#[derive(Debug, Fail)]
+pub(crate) enum ParseRawInstructionError {
+    #[fail(display = "Invalid Value: {}", _0)]
+    InvalidValue(ParseIntError),
+
+    #[fail(display = "Invalid Opcode: {}", _0)]
+    InvalidOpcode(Value),
+
+    #[fail(display = "Expected an opcode and 3 values, found {} fields", _0)]
+    WrongLength(usize),
+}
+
+impl From<ParseIntError> for ParseRawInstructionError {
+    fn from(error: ParseIntError) -> Self {
+        ParseRawInstructionError::InvalidValue(error)
+    }
+}
+
+impl FromStr for RawInstruction {
+    type Err = ParseRawInstructionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<Value> = s
+            .split_whitespace()
+            .map(|i| i.parse::<Value>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() != 4 {
+            return Err(ParseRawInstructionError::WrongLength(values.len()));
+        }
+
+        let opcode = values[0];
+        if opcode >= Opcode::all().len() as Value || opcode < 0 {
+            return Err(ParseRawInstructionError::InvalidOpcode(opcode));
+        }
+
+        let a = values[1];
+        let b = values[2];
+        let c = values[3];
+
+        Ok(RawInstruction {
+            opcode,
+            input_a: a,
+            input_b: b,
+            output: c,
+        })
+    }
+}
+
 #[derive(Debug, Fail)]
 pub(crate) enum ProgramError {
     #[fail(display = "Register error: {}", _0)]
@@ -332,24 +596,139 @@ impl From<TryFromIntError> for ProgramError {
     }
 }
 
+/// The number of registers used when neither the program nor the
+/// builder says otherwise.
+const DEFAULT_REGISTERS: usize = 6;
+
+#[derive(Debug, Fail)]
+pub(crate) enum BuildError {
+    #[fail(display = "No instruction pointer register given")]
+    NoInstructionPointer,
+
+    #[fail(display = "{} initial values given for {} registers", _1, _0)]
+    MismatchedRegisters(usize, usize),
+
+    #[fail(display = "Register error: {}", _0)]
+    Register(#[cause] RegisterError),
+}
+
+impl From<RegisterError> for BuildError {
+    fn from(error: RegisterError) -> Self {
+        BuildError::Register(error)
+    }
+}
+
+/// Configures a processor's registers before it runs.
+#[derive(Debug, Clone)]
+pub(crate) struct ProcessorBuilder {
+    commands: Vec<Instruction>,
+    instruction_pointer: Option<Value>,
+    registers: Option<usize>,
+    initial: Option<Vec<Value>>,
+    overrides: Vec<(Value, Value)>,
+    arithmetic: Arithmetic,
+}
+
+impl ProcessorBuilder {
+    pub(crate) fn new(commands: Vec<Instruction>) -> Self {
+        Self {
+            commands,
+            instruction_pointer: None,
+            registers: None,
+            initial: None,
+            overrides: Vec::new(),
+            arithmetic: Arithmetic::default(),
+        }
+    }
+
+    /// Start from a program's instructions and directives.
+    pub(crate) fn from_program(program: &program::Program) -> Self {
+        let mut builder = Processor::builder(program.instructions().to_vec());
+        if let Some(ip) = program.ip() {
+            builder = builder.with_ip(ip);
+        }
+        if let Some(registers) = program.registers() {
+            builder = builder.with_registers(registers);
+        }
+        if let Some(initial) = program.initial() {
+            builder = builder.with_initial(initial.to_vec());
+        }
+        builder
+    }
+
+    pub(crate) fn with_ip(mut self, instruction_pointer: Value) -> Self {
+        self.instruction_pointer = Some(instruction_pointer);
+        self
+    }
+
+    pub(crate) fn with_registers(mut self, registers: usize) -> Self {
+        self.registers = Some(registers);
+        self
+    }
+
+    /// Set the value of every register.
+    pub(crate) fn with_initial(mut self, initial: Vec<Value>) -> Self {
+        self.initial = Some(initial);
+        self
+    }
+
+    /// Set the value of a single register, after any initial values.
+    pub(crate) fn with_register(mut self, address: Value, value: Value) -> Self {
+        self.overrides.push((address, value));
+        self
+    }
+
+    /// Set how arithmetic overflow is handled, checked by default.
+    pub(crate) fn with_arithmetic(mut self, arithmetic: Arithmetic) -> Self {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    pub(crate) fn build(&self) -> Result<Processor, BuildError> {
+        let instruction_pointer = self
+            .instruction_pointer
+            .ok_or(BuildError::NoInstructionPointer)?;
+
+        let registers = self
+            .registers
+            .or_else(|| self.initial.as_ref().map(|v| v.len()))
+            .unwrap_or(DEFAULT_REGISTERS);
+
+        let mut register = match &self.initial {
+            Some(initial) if initial.len() != registers => {
+                return Err(BuildError::MismatchedRegisters(registers, initial.len()));
+            }
+            Some(initial) => Register::from(initial.clone()),
+            None => Register::new(registers),
+        };
+
+        register.get(instruction_pointer)?;
+        for (address, value) in &self.overrides {
+            register.store(*address, *value)?;
+        }
+
+        Ok(Processor {
+            commands: self.commands.clone(),
+            register,
+            instruction_pointer,
+            arithmetic: self.arithmetic,
+            steps: 0,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Processor {
     commands: Vec<Instruction>,
     register: Register,
     instruction_pointer: Value,
+    arithmetic: Arithmetic,
+    steps: usize,
 }
 
 impl Processor {
-    pub(crate) fn new(
-        commands: Vec<Instruction>,
-        registers: usize,
-        instruction_pointer: Value,
-    ) -> Self {
-        Self {
-            commands,
-            register: Register::new(registers),
-            instruction_pointer,
-        }
+    pub(crate) fn builder(commands: Vec<Instruction>) -> ProcessorBuilder {
+        ProcessorBuilder::new(commands)
     }
 
     pub(crate) fn ip(&self) -> Result<usize, ProgramError> {
@@ -366,22 +745,26 @@ impl Processor {
         }
 
         let instruction = self.commands[ip];
-        instruction.process(&mut self.register)?;
-        self.register.store(
-            self.instruction_pointer,
-            self.register.get(self.instruction_pointer)? + 1,
-        )?;
+        instruction
+            .process_with(&mut self.register, self.arithmetic)
+            .map_err(|error| match error {
+                RegisterError::ArithmeticOverflow => RegisterError::Overflow(ip),
+                error => error,
+            })?;
+
+        let next = self
+            .arithmetic
+            .add(self.register.get(self.instruction_pointer)?, 1)
+            .ok_or(RegisterError::Overflow(ip))?;
+        self.register.store(self.instruction_pointer, next)?;
+        self.steps += 1;
         Ok(())
     }
 
-    pub(crate) fn run(&mut self) -> Process {
-        Process { processor: self }
-    }
-
-    pub(crate) fn monitor_instruction(&mut self, instruction: usize) -> InstructionMonitor {
-        InstructionMonitor {
+    pub(crate) fn run(&mut self) -> Process<'_> {
+        Process {
             processor: self,
-            target_pointer: instruction,
+            error: None,
         }
     }
 }
@@ -389,39 +772,29 @@ impl Processor {
 #[derive(Debug)]
 pub(crate) struct Process<'p> {
     processor: &'p mut Processor,
+    error: Option<ProgramError>,
 }
 
-impl<'p> Iterator for Process<'p> {
-    type Item = Register;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.processor.step().is_err() {
-            return None;
-        }
-        Some(self.processor.register.clone())
+impl<'p> Process<'p> {
+    /// The error which stopped the program, if it didn't halt cleanly.
+    pub(crate) fn error(&self) -> Option<&ProgramError> {
+        self.error.as_ref()
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct InstructionMonitor<'p> {
-    processor: &'p mut Processor,
-    target_pointer: usize,
-}
-
-impl<'p> Iterator for InstructionMonitor<'p> {
+impl<'p> Iterator for Process<'p> {
     type Item = Register;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            if let Ok(ip) = self.processor.ip() {
-                if self.processor.step().is_err() {
-                    return None;
-                }
-                if ip == self.target_pointer {
-                    return Some(self.processor.register.clone());
-                }
-            } else {
-                return None;
+        if self.error.is_some() {
+            return None;
+        }
+        match self.processor.step() {
+            Ok(()) => Some(self.processor.register.clone()),
+            Err(ProgramError::Halted) => None,
+            Err(error) => {
+                self.error = Some(error);
+                None
             }
         }
     }
@@ -473,6 +846,23 @@ mod tests {
         assert_eq!(opcodes.len(), 16);
     }
 
+    #[test]
+    fn raw_opcodes() {
+        assert_eq!("15 1 2 3".parse::<RawInstruction>().unwrap().opcode(), 15);
+        match "16 1 2 3".parse::<RawInstruction>() {
+            Err(ParseRawInstructionError::InvalidOpcode(16)) => {}
+            other => panic!("Expected an invalid opcode, got {:?}", other),
+        }
+        assert!("-1 1 2 3".parse::<RawInstruction>().is_err());
+        for invalid in &["", "1 2", "1 2 3 4 5"] {
+            assert!(
+                invalid.parse::<RawInstruction>().is_err(),
+                "Parsed {:?}",
+                invalid
+            );
+        }
+    }
+
     #[test]
     fn register() {
         let register = Register::from([3, 2, 1, 0].to_vec());
@@ -485,9 +875,125 @@ mod tests {
         );
     }
 
+    #[test]
+    fn register_bounds() {
+        let mut register = Register::new(4);
+        assert!(register.store(3, 1).is_ok());
+        assert!(register.get(4).is_err());
+        assert!(register.store(4, 1).is_err());
+        assert!(register.get(-1).is_err());
+    }
+
+    #[test]
+    fn build_processor() {
+        let program: program::Program = "#ip 0
+#regs 3
+#init 0 0 7
+addi 2 1 1"
+            .parse()
+            .unwrap();
+
+        let mut p = ProcessorBuilder::from_program(&program)
+            .with_register(1, 5)
+            .build()
+            .unwrap();
+        assert_eq!(p.register, Register::from(vec![0, 5, 7]));
+        assert_eq!(p.run().last().unwrap(), Register::from(vec![1, 8, 7]));
+
+        let builder = Processor::builder(Vec::new());
+        match builder.build() {
+            Err(BuildError::NoInstructionPointer) => {}
+            other => panic!("Expected a missing pointer, got {:?}", other),
+        }
+        assert_eq!(
+            builder.clone().with_ip(0).build().unwrap().register,
+            Register::new(6)
+        );
+        assert!(builder.clone().with_ip(6).build().is_err());
+        match builder
+            .with_ip(0)
+            .with_registers(2)
+            .with_initial(vec![0])
+            .build()
+        {
+            Err(BuildError::MismatchedRegisters(2, 1)) => {}
+            other => panic!("Expected mismatched registers, got {:?}", other),
+        }
+    }
+
     #[test]
     fn instructions() {
         let i: Instruction = "seti 5 0 1".parse().unwrap();
         assert_eq!(i, Instruction::new(Opcode::Seti, 5, 0, 1));
     }
+
+    #[test]
+    fn display_instructions() {
+        for oc in Opcode::all() {
+            let i = Instruction::new(oc, 1, -2, 3);
+            assert_eq!(i.to_string().parse::<Instruction>().unwrap(), i);
+        }
+        assert_eq!(
+            Instruction::new(Opcode::Addi, 1, 2, 3).to_string(),
+            "addi 1 2 3"
+        );
+    }
+
+    fn overflowing_program(arithmetic: Arithmetic) -> Processor {
+        let commands = vec![
+            Instruction::new(Opcode::Seti, Value::MAX - 1, 0, 1),
+            Instruction::new(Opcode::Muli, 1, 2, 1),
+        ];
+        Processor::builder(commands)
+            .with_ip(0)
+            .with_registers(2)
+            .with_arithmetic(arithmetic)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let mut p = overflowing_program(Arithmetic::Checked);
+        p.step().unwrap();
+        match p.step() {
+            Err(ProgramError::Register(RegisterError::Overflow(ip))) => assert_eq!(ip, 1),
+            other => panic!("Expected an overflow, got {:?}", other),
+        }
+
+        let mut p = overflowing_program(Arithmetic::Checked);
+        let mut run = p.run();
+        assert_eq!(run.by_ref().count(), 1);
+        match run.error() {
+            Some(ProgramError::Register(RegisterError::Overflow(1))) => {}
+            other => panic!("Expected an overflow, got {:?}", other),
+        }
+        assert!(run.next().is_none());
+    }
+
+    #[test]
+    fn wrapping_arithmetic() {
+        let mut p = overflowing_program(Arithmetic::Wrapping);
+        let mut run = p.run();
+        let r = run.by_ref().last().unwrap();
+        assert_eq!(r.get(1).unwrap(), -4);
+        assert!(run.error().is_none());
+    }
+
+    #[test]
+    fn saturating_arithmetic() {
+        let mut p = overflowing_program(Arithmetic::Saturating);
+        let r = p.run().last().unwrap();
+        assert_eq!(r.get(1).unwrap(), Value::MAX);
+    }
+
+    #[test]
+    fn process_checks_overflow() {
+        let mut register: Register = vec![Value::MAX, 2].into();
+        let add = Instruction::new(Opcode::Addr, 0, 1, 0);
+        match add.process(&mut register) {
+            Err(RegisterError::ArithmeticOverflow) => {}
+            other => panic!("Expected an overflow, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,352 @@
+//! Watch a running processor for conditions on its state.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::rc::Rc;
+
+use super::{Instruction, Processor, ProgramError, Register, Value};
+
+/// How to compare a register against a value.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[cfg(test)]
+impl Comparison {
+    fn compare(self, lhs: Value, rhs: Value) -> bool {
+        match self {
+            Comparison::Equal => lhs == rhs,
+            Comparison::NotEqual => lhs != rhs,
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+}
+
+type Predicate = dyn Fn(usize, &Instruction, &Register) -> bool;
+
+/// A condition checked after each instruction is executed.
+#[derive(Clone)]
+pub(crate) enum Watch {
+    /// The instruction executed was at one of these locations.
+    Ip(BTreeSet<usize>),
+
+    /// The instruction executed reads this register.
+    Reads(Value),
+
+    /// The instruction changed the value of this register.
+    Changed(Value),
+
+    /// The register compares with this value after the instruction.
+    #[cfg(test)]
+    Compare(Value, Comparison, Value),
+
+    /// Any condition on the location, instruction and resulting registers.
+    Predicate(Rc<Predicate>),
+
+    And(Box<Watch>, Box<Watch>),
+    #[cfg(test)]
+    Or(Box<Watch>, Box<Watch>),
+}
+
+impl fmt::Debug for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Watch::Ip(ips) => write!(f, "Ip({:?})", ips),
+            Watch::Reads(r) => write!(f, "Reads({})", r),
+            Watch::Changed(r) => write!(f, "Changed({})", r),
+            #[cfg(test)]
+            Watch::Compare(r, c, v) => write!(f, "Compare({}, {:?}, {})", r, c, v),
+            Watch::Predicate(_) => write!(f, "Predicate"),
+            Watch::And(a, b) => write!(f, "And({:?}, {:?})", a, b),
+            #[cfg(test)]
+            Watch::Or(a, b) => write!(f, "Or({:?}, {:?})", a, b),
+        }
+    }
+}
+
+impl Watch {
+    pub(crate) fn ip(ip: usize) -> Self {
+        Watch::ips(Some(ip))
+    }
+
+    pub(crate) fn ips<I: IntoIterator<Item = usize>>(ips: I) -> Self {
+        Watch::Ip(ips.into_iter().collect())
+    }
+
+    pub(crate) fn reads(register: Value) -> Self {
+        Watch::Reads(register)
+    }
+
+    pub(crate) fn changed(register: Value) -> Self {
+        Watch::Changed(register)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn compare(register: Value, comparison: Comparison, value: Value) -> Self {
+        Watch::Compare(register, comparison, value)
+    }
+
+    pub(crate) fn predicate<F>(f: F) -> Self
+    where
+        F: Fn(usize, &Instruction, &Register) -> bool + 'static,
+    {
+        Watch::Predicate(Rc::new(f))
+    }
+
+    pub(crate) fn and(self, other: Watch) -> Self {
+        Watch::And(Box::new(self), Box::new(other))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn or(self, other: Watch) -> Self {
+        Watch::Or(Box::new(self), Box::new(other))
+    }
+
+    fn check(
+        &self,
+        ip: usize,
+        instruction: &Instruction,
+        before: &Register,
+        after: &Register,
+    ) -> bool {
+        match self {
+            Watch::Ip(ips) => ips.contains(&ip),
+            Watch::Reads(r) => instruction.reads().contains(r),
+            Watch::Changed(r) => before.get(*r).ok() != after.get(*r).ok(),
+            #[cfg(test)]
+            Watch::Compare(r, c, v) => after.get(*r).map(|a| c.compare(a, *v)).unwrap_or(false),
+            Watch::Predicate(f) => f(ip, instruction, after),
+            Watch::And(a, b) => {
+                a.check(ip, instruction, before, after) && b.check(ip, instruction, before, after)
+            }
+            #[cfg(test)]
+            Watch::Or(a, b) => {
+                a.check(ip, instruction, before, after) || b.check(ip, instruction, before, after)
+            }
+        }
+    }
+}
+
+/// A point where a watch matched.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Hit {
+    step: usize,
+    ip: usize,
+    instruction: Instruction,
+    register: Register,
+}
+
+impl Hit {
+    /// The number of instructions executed, including this one.
+    #[cfg(test)]
+    pub(crate) fn step(&self) -> usize {
+        self.step
+    }
+
+    /// The location of the instruction which matched.
+    pub(crate) fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub(crate) fn instruction(&self) -> Instruction {
+        self.instruction
+    }
+
+    /// The registers after the instruction executed.
+    pub(crate) fn register(&self) -> &Register {
+        &self.register
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Monitor<'p> {
+    processor: &'p mut Processor,
+    watch: Watch,
+    before: Register,
+    error: Option<ProgramError>,
+}
+
+impl<'p> Monitor<'p> {
+    /// The error which stopped the program, if it didn't halt cleanly.
+    pub(crate) fn error(&self) -> Option<&ProgramError> {
+        self.error.as_ref()
+    }
+}
+
+impl<'p> Iterator for Monitor<'p> {
+    type Item = Hit;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        loop {
+            let ip = match self.processor.ip() {
+                Ok(ip) => ip,
+                Err(error) => {
+                    self.error = Some(error);
+                    return None;
+                }
+            };
+            let instruction = *self.processor.commands.get(ip)?;
+            self.before.clone_from(&self.processor.register);
+
+            if let Err(error) = self.processor.step() {
+                self.error = Some(error);
+                return None;
+            }
+
+            let after = &self.processor.register;
+            if self.watch.check(ip, &instruction, &self.before, after) {
+                return Some(Hit {
+                    step: self.processor.steps,
+                    ip,
+                    instruction,
+                    register: after.clone(),
+                });
+            }
+        }
+    }
+}
+
+impl Processor {
+    /// Run the processor, stopping each time the watch matches.
+    pub(crate) fn monitor(&mut self, watch: Watch) -> Monitor<'_> {
+        let before = self.register.clone();
+        Monitor {
+            processor: self,
+            watch,
+            before,
+            error: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::elfcode::program::Program;
+    use crate::elfcode::{ProcessorBuilder, RegisterError};
+
+    fn processor(source: &str) -> Processor {
+        let program: Program = source.parse().unwrap();
+        ProcessorBuilder::from_program(&program).build().unwrap()
+    }
+
+    fn example_program() -> Processor {
+        processor(
+            "#ip 0
+seti 5 0 1
+seti 6 0 2
+addi 0 1 0
+addr 1 2 3
+setr 1 0 0
+seti 8 0 4
+seti 9 0 5",
+        )
+    }
+
+    #[test]
+    fn watch_ip() {
+        let mut p = example_program();
+        let hits: Vec<_> = p.monitor(Watch::ips(vec![1, 6])).collect();
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].ip(), 1);
+        assert_eq!(hits[0].step(), 2);
+        assert_eq!(hits[1].ip(), 6);
+        assert_eq!(hits[1].register().get(5).unwrap(), 9);
+    }
+
+    #[test]
+    fn watch_changes() {
+        let mut p = example_program();
+        let steps: Vec<_> = p
+            .monitor(Watch::changed(1).or(Watch::changed(5)))
+            .map(|h| h.step())
+            .collect();
+        assert_eq!(steps, vec![1, 5]);
+    }
+
+    #[test]
+    fn watch_comparison() {
+        let mut p = example_program();
+        let hit = p
+            .monitor(Watch::compare(2, Comparison::Greater, 5).and(Watch::reads(1)))
+            .next()
+            .unwrap();
+        assert_eq!(hit.ip(), 4);
+        assert_eq!(hit.instruction(), "setr 1 0 0".parse().unwrap());
+    }
+
+    #[test]
+    fn comparisons() {
+        let results: Vec<_> = [
+            Comparison::Equal,
+            Comparison::NotEqual,
+            Comparison::Less,
+            Comparison::LessOrEqual,
+            Comparison::Greater,
+            Comparison::GreaterOrEqual,
+        ]
+        .iter()
+        .map(|c| (c.compare(1, 2), c.compare(2, 2), c.compare(3, 2)))
+        .collect();
+
+        assert_eq!(
+            results,
+            vec![
+                (false, true, false),
+                (true, false, true),
+                (true, false, false),
+                (true, true, false),
+                (false, false, true),
+                (false, true, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn watch_predicate() {
+        let mut p = example_program();
+        let hit = p
+            .monitor(Watch::predicate(|ip, _, r| ip > 2 && r.get(5).unwrap() > 0))
+            .next()
+            .unwrap();
+        assert_eq!(hit.ip(), 6);
+        assert_eq!(hit.step(), 5);
+    }
+
+    #[test]
+    fn watch_overflow() {
+        let mut p = processor(&format!(
+            "#ip 0
+#regs 2
+seti {} 0 1
+addi 1 1 1",
+            Value::MAX
+        ));
+        let mut monitor = p.monitor(Watch::ips(vec![0, 1]));
+        assert_eq!(monitor.by_ref().count(), 1);
+        match monitor.error() {
+            Some(ProgramError::Register(RegisterError::Overflow(1))) => {}
+            other => panic!("Expected an overflow, got {:?}", other),
+        }
+
+        let mut p = example_program();
+        let mut monitor = p.monitor(Watch::ips(vec![6]));
+        assert_eq!(monitor.by_ref().count(), 1);
+        assert!(monitor.error().is_none());
+    }
+}
@@ -0,0 +1,266 @@
+//! Elfcode programs as source text, with their directives.
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::ops::Index;
+use std::str::FromStr;
+
+use failure::Fail;
+use itertools::Itertools;
+
+use super::{Instruction, InstructionPointer, ParseIPError, ParseInstructionError, Value};
+
+/// A list of instructions, along with the directives which describe
+/// the machine they run on:
+///
+/// - `#ip 4` binds register 4 to the instruction pointer.
+/// - `#regs 6` sets the number of registers.
+/// - `#init 1 0 0 0 0 0` sets the initial value of each register.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Program {
+    ip: Option<Value>,
+    registers: Option<usize>,
+    initial: Option<Vec<Value>>,
+    instructions: Vec<Instruction>,
+}
+
+impl From<Vec<Instruction>> for Program {
+    fn from(instructions: Vec<Instruction>) -> Self {
+        Self {
+            instructions,
+            ..Self::default()
+        }
+    }
+}
+
+impl Index<usize> for Program {
+    type Output = Instruction;
+
+    fn index(&self, index: usize) -> &Instruction {
+        &self.instructions[index]
+    }
+}
+
+impl Program {
+    pub(crate) fn new(instructions: Vec<Instruction>, ip: Option<Value>) -> Self {
+        Self {
+            ip,
+            instructions,
+            ..Self::default()
+        }
+    }
+
+    /// Declare the number of registers this program runs with.
+    pub(crate) fn with_registers(mut self, registers: usize) -> Self {
+        self.registers = Some(registers);
+        self
+    }
+
+    /// Declare the initial values of the registers.
+    pub(crate) fn with_initial(mut self, initial: Vec<Value>) -> Self {
+        self.initial = Some(initial);
+        self
+    }
+
+    /// The register bound to the instruction pointer.
+    pub(crate) fn ip(&self) -> Option<Value> {
+        self.ip
+    }
+
+    /// The number of registers declared with `#regs`.
+    pub(crate) fn registers(&self) -> Option<usize> {
+        self.registers
+    }
+
+    /// The initial register values declared with `#init`.
+    pub(crate) fn initial(&self) -> Option<&[Value]> {
+        self.initial.as_deref()
+    }
+
+    /// This program's directives, without any instructions.
+    pub(crate) fn directives(&self) -> Self {
+        Self {
+            instructions: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.instructions.len()
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ip) = self.ip {
+            writeln!(f, "#ip {}", ip)?;
+        }
+        if let Some(registers) = self.registers {
+            writeln!(f, "#regs {}", registers)?;
+        }
+        if let Some(initial) = &self.initial {
+            writeln!(f, "#init {}", initial.iter().join(" "))?;
+        }
+        for instruction in &self.instructions {
+            writeln!(f, "{}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Fail)]
+pub(crate) enum ParseProgramError {
+    #[fail(display = "Line {}: {}", _0, _1)]
+    Pointer(usize, #[cause] ParseIPError),
+
+    #[fail(display = "Line {}: {}", _0, _1)]
+    Instruction(usize, #[cause] ParseInstructionError),
+
+    #[fail(display = "Line {}: Invalid value: {}", _0, _1)]
+    InvalidValue(usize, #[cause] ParseIntError),
+
+    #[fail(display = "Line {}: Unknown directive {}", _0, _1)]
+    UnknownDirective(usize, String),
+
+    #[fail(display = "{} initial values given for {} registers", _1, _0)]
+    MismatchedRegisters(usize, usize),
+}
+
+impl FromStr for Program {
+    type Err = ParseProgramError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut program = Program::default();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            let n = i + 1;
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if !line.starts_with('#') {
+                program.instructions.push(
+                    line.parse()
+                        .map_err(|e| ParseProgramError::Instruction(n, e))?,
+                );
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some("#ip") => {
+                    let ip: InstructionPointer =
+                        line.parse().map_err(|e| ParseProgramError::Pointer(n, e))?;
+                    program.ip = Some(ip.into());
+                }
+                Some("#regs") => {
+                    let registers = parts
+                        .next()
+                        .unwrap_or("")
+                        .parse::<usize>()
+                        .map_err(|e| ParseProgramError::InvalidValue(n, e))?;
+                    program.registers = Some(registers);
+                }
+                Some("#init") => {
+                    let initial = parts
+                        .map(|v| v.parse::<Value>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| ParseProgramError::InvalidValue(n, e))?;
+                    program.initial = Some(initial);
+                }
+                _ => return Err(ParseProgramError::UnknownDirective(n, line.to_string())),
+            }
+        }
+
+        if let (Some(registers), Some(initial)) = (program.registers, &program.initial) {
+            if registers != initial.len() {
+                return Err(ParseProgramError::MismatchedRegisters(
+                    registers,
+                    initial.len(),
+                ));
+            }
+        }
+
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::elfcode::Opcode;
+
+    #[test]
+    fn parse_program() {
+        let program: Program = "#ip 0
+seti 5 0 1
+addi 0 1 0"
+            .parse()
+            .unwrap();
+
+        assert_eq!(program.ip(), Some(0));
+        assert_eq!(program.len(), 2);
+        assert_eq!(program[1], Instruction::new(Opcode::Addi, 0, 1, 0));
+    }
+
+    #[test]
+    fn round_trip() {
+        use crate::input_to_string;
+
+        for day in &[19, 21] {
+            let program: Program = input_to_string(*day).unwrap().parse().unwrap();
+            assert_eq!(program.to_string().parse::<Program>().unwrap(), program);
+        }
+    }
+
+    #[test]
+    fn register_directives() {
+        let program: Program = "#ip 2
+#regs 3
+#init 1 0 0
+seti 5 0 1"
+            .parse()
+            .unwrap();
+
+        assert_eq!(program.registers(), Some(3));
+        assert_eq!(program.initial(), Some(&[1, 0, 0][..]));
+        assert_eq!(program.to_string().parse::<Program>().unwrap(), program);
+
+        match "#regs 2\n#init 1 0 0".parse::<Program>() {
+            Err(ParseProgramError::MismatchedRegisters(2, 3)) => {}
+            other => panic!("Expected mismatched registers, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_directive() {
+        match "#ip 1\n#foo 2".parse::<Program>() {
+            Err(ParseProgramError::UnknownDirective(2, _)) => {}
+            other => panic!("Expected an unknown directive, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn short_line() {
+        let error = "#ip 0\nseti 5 0 1\naddi 1 2"
+            .parse::<Program>()
+            .unwrap_err();
+        match &error {
+            ParseProgramError::Instruction(3, ParseInstructionError::WrongLength(3)) => {}
+            other => panic!("Expected a short line, got {:?}", other),
+        }
+        assert_eq!(
+            error.to_string(),
+            "Line 3: Expected an opcode and 3 values, found 3 fields"
+        );
+
+        assert!("seti 5 0 1 2".parse::<Program>().is_err());
+    }
+}
@@ -1,15 +1,83 @@
-use super::{Instruction, Opcode, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{Arithmetic, Instruction, Opcode, Operand, Register, Value};
 
 const VARS: &str = "abcdefghijklmnopqrstuvwxyz";
 
-fn var(register: Value) -> String {
-    format!("{}", VARS.chars().nth(register as usize).unwrap())
+/// Names used for registers when rendering pseudocode.
+///
+/// The register bound to the instruction pointer is always shown as `ip`.
+#[derive(Debug, Clone)]
+pub(crate) struct RegisterNames {
+    names: BTreeMap<Value, String>,
+    ip: Value,
+}
+
+impl RegisterNames {
+    /// Default names, `a`, `b`, `c`... for each register.
+    pub(crate) fn new(ip: Value) -> Self {
+        Self {
+            names: BTreeMap::new(),
+            ip,
+        }
+    }
+
+    /// Use a custom name for a register.
+    pub(crate) fn with_name(mut self, register: Value, name: &str) -> Self {
+        self.names.insert(register, name.to_string());
+        self
+    }
+
+    pub(crate) fn name(&self, register: Value) -> String {
+        if register == self.ip {
+            return "ip".to_string();
+        }
+        if let Some(name) = self.names.get(&register) {
+            return name.clone();
+        }
+        VARS.chars()
+            .nth(register as usize)
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| format!("r{}", register))
+    }
 }
 
 pub trait Decompile {
     fn decompile(&self) -> String;
 }
 
+fn symbol(opcode: Opcode) -> Option<&'static str> {
+    match opcode {
+        Opcode::Addr | Opcode::Addi => Some("+"),
+        Opcode::Mulr | Opcode::Muli => Some("*"),
+        Opcode::Banr | Opcode::Bani => Some("&"),
+        Opcode::Borr | Opcode::Bori => Some("|"),
+        Opcode::Gtir | Opcode::Gtri | Opcode::Gtrr => Some(">"),
+        Opcode::Eqir | Opcode::Eqri | Opcode::Eqrr => Some("=="),
+        Opcode::Setr | Opcode::Seti => None,
+    }
+}
+
+fn is_comparison(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::Gtir | Opcode::Gtri | Opcode::Gtrr | Opcode::Eqir | Opcode::Eqri | Opcode::Eqrr
+    )
+}
+
+/// Where an instruction which writes to the instruction pointer goes.
+#[derive(Debug, Clone, PartialEq)]
+enum Jump {
+    /// The target is known statically.
+    Goto(Value),
+
+    /// Jump to the target only when the condition holds.
+    Conditional(String, Value),
+
+    /// The target depends on a register we can't reason about.
+    Computed,
+}
+
 #[derive(Debug)]
 pub(crate) struct LInstruction {
     command: Instruction,
@@ -21,68 +89,244 @@ impl LInstruction {
         Self { command, label }
     }
 
-    fn f_rr(&self, op: &str) -> String {
-        format!(
-            "{} = {} {} {}",
-            var(self.command.output),
-            var(self.command.input_a),
-            op,
-            var(self.command.input_b)
-        )
+    /// Reading the instruction pointer always gives the current label.
+    fn operand(&self, kind: Operand, value: Value, names: &RegisterNames) -> String {
+        match kind {
+            Operand::Register if value == names.ip => self.label.to_string(),
+            Operand::Register => names.name(value),
+            Operand::Immediate | Operand::Unused => value.to_string(),
+        }
     }
-    fn f_ri(&self, op: &str) -> String {
-        format!(
-            "{} = {} {} {}",
-            var(self.command.output),
-            var(self.command.input_a),
-            op,
-            self.command.input_b
-        )
+
+    /// The right hand side of this instruction.
+    fn expression(&self, names: &RegisterNames) -> String {
+        let (kind_a, kind_b) = self.command.opcode.operands();
+        let a = self.operand(kind_a, self.command.input_a, names);
+        match symbol(self.command.opcode) {
+            Some(op) => format!(
+                "{} {} {}",
+                a,
+                op,
+                self.operand(kind_b, self.command.input_b, names)
+            ),
+            None => a,
+        }
     }
-    fn f_ir(&self, op: &str) -> String {
+
+    fn assignment(&self, names: &RegisterNames) -> String {
         format!(
-            "{} = {} {} {}",
-            var(self.command.output),
-            self.command.input_a,
-            op,
-            var(self.command.input_b)
+            "{} = {}",
+            names.name(self.command.output),
+            self.expression(names)
         )
     }
+
+    /// Resolve the jump made by this instruction, if it writes to the
+    /// instruction pointer. The previous instruction is used to recognize
+    /// conditional jumps, i.e. `addr x ip ip` where `x` is a comparison.
+    fn jump(&self, previous: Option<&LInstruction>, names: &RegisterNames) -> Option<Jump> {
+        let ip = names.ip;
+        if self.command.writes() != ip {
+            return None;
+        }
+
+        let inputs = self.command.reads();
+
+        // Only the instruction pointer is read, and its value is our label.
+        // A target too large to represent is still past the end of the
+        // program, so saturate rather than losing the jump.
+        if inputs.iter().all(|&r| r == ip) {
+            let mut register = Register::new(ip as usize + 1);
+            register.store(ip, self.label).ok()?;
+            self.command
+                .process_with(&mut register, Arithmetic::Saturating)
+                .ok()?;
+            return Some(Jump::Goto(register.get(ip).ok()?.saturating_add(1)));
+        }
+
+        if self.command.opcode == Opcode::Addr && inputs.contains(&ip) {
+            let flag = inputs.iter().cloned().find(|&r| r != ip)?;
+            let condition = previous
+                .filter(|p| p.command.writes() == flag && is_comparison(p.command.opcode))
+                .map(|p| p.expression(names));
+            if let Some(condition) = condition {
+                return Some(Jump::Conditional(condition, self.label + 2));
+            }
+        }
+
+        Some(Jump::Computed)
+    }
 }
 
-fn instruction_to_pseudocode(ins: LInstruction, ip: Value) -> String {
-    let cmd = match ins.command.opcode {
-        Opcode::Addr => ins.f_rr("+"),
-        Opcode::Addi => ins.f_ri("+"),
-        Opcode::Mulr => ins.f_rr("*"),
-        Opcode::Muli => ins.f_ri("*"),
-        Opcode::Banr => ins.f_rr("&"),
-        Opcode::Bani => ins.f_ri("&"),
-        Opcode::Borr => ins.f_rr("|"),
-        Opcode::Bori => ins.f_ri("|"),
-        Opcode::Setr => format!("{} = {}", var(ins.command.output), var(ins.command.input_a)),
-        Opcode::Seti => format!("{} = {}", var(ins.command.output), ins.command.input_a),
-        Opcode::Gtir => ins.f_ir(">"),
-        Opcode::Gtri => ins.f_ri(">"),
-        Opcode::Gtrr => ins.f_rr(">"),
-        Opcode::Eqir => ins.f_ir("=="),
-        Opcode::Eqri => ins.f_ri("=="),
-        Opcode::Eqrr => ins.f_rr("=="),
-    };
-
-    if ins.command.output == ip {
-        format!("[{:2}] jump {}", ins.label, cmd,)
+fn target(label: Value, length: usize) -> String {
+    if label < 0 || label as usize >= length {
+        "halt".to_string()
     } else {
-        format!("[{:2}] {}", ins.label, cmd)
+        format!("goto L{}", label)
     }
 }
 
-pub(crate) fn decompile(program: &[Instruction], ip: Value) -> String {
-    let program = program
+/// Every label which a resolved jump goes to.
+fn targets(jumps: &[Option<Jump>]) -> BTreeSet<Value> {
+    jumps
+        .iter()
+        .filter_map(|j| match j {
+            Some(Jump::Goto(t)) | Some(Jump::Conditional(_, t)) => Some(*t),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Decompile to pseudocode, resolving jumps into labelled `goto` statements.
+pub(crate) fn decompile(program: &[Instruction], names: &RegisterNames) -> String {
+    let lines = program
         .iter()
         .cloned()
         .enumerate()
-        .map(|(i, c)| instruction_to_pseudocode(LInstruction::new(c, i as Value), ip))
+        .map(|(i, c)| LInstruction::new(c, i as Value))
+        .collect::<Vec<_>>();
+
+    let mut jumps = lines
+        .iter()
+        .enumerate()
+        .map(|(i, ins)| ins.jump(if i > 0 { lines.get(i - 1) } else { None }, names))
         .collect::<Vec<_>>();
-    program.join("\n")
+
+    // A conditional jump which is itself a jump target might be reached
+    // without running the comparison before it, so its flag is unknown.
+    let reached = targets(&jumps);
+    for (ins, jump) in lines.iter().zip(jumps.iter_mut()) {
+        if let Some(Jump::Conditional(..)) = jump {
+            if reached.contains(&ins.label) {
+                *jump = Some(Jump::Computed);
+            }
+        }
+    }
+    let targets = targets(&jumps);
+
+    let mut output = Vec::with_capacity(lines.len());
+    for (ins, jump) in lines.iter().zip(jumps) {
+        if targets.contains(&ins.label) {
+            output.push(format!("L{}:", ins.label));
+        }
+
+        let cmd = match jump {
+            None => ins.assignment(names),
+            Some(Jump::Goto(t)) => target(t, lines.len()),
+            Some(Jump::Conditional(condition, t)) => {
+                format!("if ({}) {}", condition, target(t, lines.len()))
+            }
+            Some(Jump::Computed) => format!("jump {}", ins.assignment(names)),
+        };
+        output.push(format!("[{:2}] {}", ins.label, cmd));
+    }
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(s: &str) -> Vec<Instruction> {
+        s.lines().map(|l| l.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn absolute_and_relative_jumps() {
+        let p = program(
+            "addi 4 2 4
+seti 0 0 4
+seti 5 0 1
+mulr 4 4 4",
+        );
+
+        let code = decompile(&p, &RegisterNames::new(4));
+        let lines: Vec<_> = code.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "[ 0] goto L3",
+                "L1:",
+                "[ 1] goto L1",
+                "[ 2] b = 5",
+                "L3:",
+                "[ 3] halt"
+            ]
+        );
+    }
+
+    #[test]
+    fn overflowing_jumps() {
+        let p = program(&format!("seti 1 0 1\nmuli 0 {} 0", Value::MAX));
+        assert_eq!(
+            decompile(&p, &RegisterNames::new(0)),
+            "[ 0] b = 1\n[ 1] halt"
+        );
+    }
+
+    fn flag_names() -> RegisterNames {
+        RegisterNames::new(3)
+            .with_name(0, "x")
+            .with_name(1, "flag")
+            .with_name(2, "limit")
+            .with_name(4, "count")
+    }
+
+    #[test]
+    fn conditional_jumps() {
+        let p = program(
+            "gtrr 4 2 1
+addr 1 3 3
+seti -1 0 3
+seti 9 0 0",
+        );
+
+        let code = decompile(&p, &flag_names());
+        let lines: Vec<_> = code.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "L0:",
+                "[ 0] flag = count > limit",
+                "[ 1] if (count > limit) goto L3",
+                "[ 2] goto L0",
+                "L3:",
+                "[ 3] x = 9"
+            ]
+        );
+    }
+
+    #[test]
+    fn conditional_jump_targets() {
+        // Jumping straight to the `addr` skips the comparison, so the
+        // flag could hold anything.
+        let p = program(
+            "gtrr 4 2 1
+addr 1 3 3
+seti 0 0 3
+seti 9 0 0",
+        );
+
+        let code = decompile(&p, &flag_names());
+        let lines: Vec<_> = code.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "[ 0] flag = count > limit",
+                "L1:",
+                "[ 1] jump ip = flag + 1",
+                "[ 2] goto L1",
+                "[ 3] x = 9"
+            ]
+        );
+    }
+
+    #[test]
+    fn computed_jumps() {
+        let p = program("addr 4 0 4");
+        assert_eq!(
+            decompile(&p, &RegisterNames::new(4)),
+            "[ 0] jump ip = 0 + a"
+        );
+    }
 }
@@ -14,14 +14,22 @@ mod elfcode;
 pub(crate) mod iterhelper;
 mod puzzles;
 
+use crate::elfcode::encoding::OpcodeTable;
+use crate::elfcode::program::Program;
+
 const USAGE: &str = "
 Advent of Code 2018.
 
-Solves a given day's puzzle.
+Solves a given day's puzzle, or assembles and disassembles
+elfcode programs.
 
 Usage:
+    aoc2018 assemble <program> <binary>
+    aoc2018 disassemble [--numeric] <binary>
     aoc2018 <day>
 
+Options:
+    --numeric  Print numeric opcodes, like day 16, in place of names.
 ";
 
 macro_rules! day {
@@ -32,7 +40,12 @@ macro_rules! day {
 
 #[derive(Deserialize)]
 struct Args {
-    arg_day: usize,
+    cmd_assemble: bool,
+    cmd_disassemble: bool,
+    arg_day: Option<usize>,
+    arg_program: String,
+    arg_binary: String,
+    flag_numeric: bool,
 }
 
 pub fn input(day: usize) -> std::io::Result<Box<::std::io::BufRead>> {
@@ -50,12 +63,41 @@ pub fn input_to_string(day: usize) -> std::io::Result<String> {
     Ok(buffer)
 }
 
+/// Write an elfcode program in the compact binary encoding.
+fn assemble(args: &Args) -> Result<(), Error> {
+    let program: Program = std::fs::read_to_string(&args.arg_program)?.parse()?;
+    std::fs::write(&args.arg_binary, program.to_bytes())?;
+    Ok(())
+}
+
+/// Print an elfcode binary as source.
+fn disassemble(args: &Args) -> Result<(), Error> {
+    let program = Program::from_bytes(&std::fs::read(&args.arg_binary)?)?;
+    if args.flag_numeric {
+        print!("{}", program.directives());
+        for instruction in OpcodeTable::standard().encode_program(&program)? {
+            println!("{}", instruction);
+        }
+    } else {
+        print!("{}", program);
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Error> {
     let args: Args = Docopt::new(USAGE)
         .and_then(|dopt| dopt.deserialize())
         .unwrap_or_else(|e| e.exit());
 
-    println!("Solving AoC for Day {}", args.arg_day);
+    if args.cmd_assemble {
+        return assemble(&args);
+    }
+    if args.cmd_disassemble {
+        return disassemble(&args);
+    }
+
+    let day = args.arg_day.unwrap_or(0);
+    println!("Solving AoC for Day {}", day);
 
     let solvers: Vec<Box<Fn() -> Result<(), Error>>> = vec![
         day!(day1),
@@ -83,10 +125,10 @@ fn main() -> Result<(), Error> {
         day!(day23),
     ];
 
-    if args.arg_day > solvers.len() || args.arg_day < 1 {
-        eprintln!("Can't solve puzzle for day {}", args.arg_day);
+    if day > solvers.len() || day < 1 {
+        eprintln!("Can't solve puzzle for day {}", day);
         Ok(())
     } else {
-        (solvers[args.arg_day - 1])()
+        (solvers[day - 1])()
     }
 }
@@ -4,7 +4,9 @@ use std::str::FromStr;
 
 use failure::{format_err, Error};
 
+use geometry::{BoundingBox, Position};
 use itertools::iproduct;
+use rayon::prelude::*;
 
 type Result<T> = ::std::result::Result<T, Error>;
 
@@ -55,6 +57,12 @@ impl Coordinate {
     }
 }
 
+impl From<geometry::Point> for Coordinate {
+    fn from(point: geometry::Point) -> Self {
+        Self::new(Element::from(point.x), Element::from(point.y))
+    }
+}
+
 impl FromStr for Coordinate {
     type Err = Error;
 
@@ -119,31 +127,35 @@ impl Grid {
         self.vpatch(position, 3)
     }
 
+    /// The top left corner of every patch of `size` cells which fits
+    /// in the grid.
+    fn corners(size: Position) -> BoundingBox {
+        BoundingBox::new(1, 301 - size, 1, 301 - size)
+    }
+
     fn max_patch(&self) -> Option<(Coordinate, Element)> {
-        iproduct!(1..298, 1..298)
-            .map(|(x, y)| {
-                let c = Coordinate::new(x, y);
+        Self::corners(3)
+            .par_points()
+            .map(|p| {
+                let c = Coordinate::from(p);
                 (c, self.patch(&c))
             })
             .max_by_key(|(_, p)| *p)
     }
 
     fn max_vpatch(&self) -> Option<(Coordinate, Element, Element)> {
-        use rayon::prelude::*;
-
-        (1i64..301)
-            .into_par_iter()
-            .map(|s| {
-                iproduct!(1..298, 1..298)
-                    .filter(|(x, y)| (x + s <= 300) && (y + s <= 300))
-                    .map(|(x, y)| {
-                        let c = Coordinate::new(x, y);
+        (1..=300)
+            .filter_map(|size| {
+                let s = Element::from(size);
+                Self::corners(size)
+                    .par_points()
+                    .map(|p| {
+                        let c = Coordinate::from(p);
                         (c, s, self.vpatch(&c, s))
                     })
                     .max_by_key(|(_, _, p)| *p)
             })
-            .max_by_key(|v| v.map(|(_, _, p)| p).unwrap_or(0))
-            .unwrap_or(None)
+            .max_by_key(|(_, _, p)| *p)
     }
 }
 
@@ -4,6 +4,7 @@ use std::io::BufRead;
 use std::str::FromStr;
 
 use failure::{format_err, Error};
+use geometry::LineAutomaton;
 
 macro_rules! err {
     ($($tt:tt)*) => { Err(format_err!($($tt)*)) }
@@ -35,44 +36,42 @@ fn get_pots() -> Result<(Pots, Vec<Note>)> {
     Ok((pots, notes))
 }
 
-fn evolve_until_stable(pots: Pots, notes: &[Note], maxiter: Plant) -> (Pots, Plant) {
-    let mut pots = pots;
-    let mut offset = 0;
-    let mut same = 0;
-    {
-        let mut seq = pots.sequence();
-        for i in 1..maxiter {
-            pots = pots.grow_once(&notes);
-
-            if pots.sequence() == seq && i > 20 {
-                same += 1;
-                if same > 2 {
-                    offset = i;
-                    break;
-                }
-            } else {
-                seq = pots.sequence();
-            }
-        }
+/// Pots grow by looking two pots to either side.
+const RADIUS: usize = 2;
+
+/// Turn the notes into a lookup table, indexed by the pattern of
+/// plants around a pot read as a binary number.
+fn rule(notes: &[Note]) -> impl FnMut(&[bool]) -> bool {
+    let mut table = [false; 1 << (2 * RADIUS + 1)];
+    for note in notes.iter().filter(|n| n.grow) {
+        table[pattern((-2..=2).map(|p| note.plants.contains(&p)))] = true;
     }
-    (pots, offset)
+    move |window: &[bool]| table[pattern(window.iter().cloned())]
+}
+
+fn pattern<I: Iterator<Item = bool>>(plants: I) -> usize {
+    plants.fold(0, |n, plant| (n << 1) | plant as usize)
+}
+
+fn garden(pots: &Pots, notes: &[Note]) -> Result<LineAutomaton<bool, impl FnMut(&[bool]) -> bool>> {
+    let (first, last) = pots.range();
+    let cells = (first..=last).map(|p| pots.0.contains(&p)).collect();
+    Ok(LineAutomaton::new(cells, false, RADIUS, rule(notes))?.with_offset(first))
 }
 
 pub(crate) fn main() -> Result<()> {
     let (pots, notes) = get_pots()?;
 
     {
-        let mut pots = pots.clone();
-        for _ in 0..20 {
-            pots = pots.grow_once(&notes);
-        }
-        println!("Part 1: {}", pots.total());
+        let mut garden = garden(&pots, &notes)?;
+        garden.advance_to(20);
+        println!("Part 1: {}", Pots::from(&garden).total());
     }
 
     {
-        let iterations = 50_000_000_000i64;
-        let (pots, offset) = evolve_until_stable(pots, &notes, iterations);
-        println!("Part 2: {}", pots.total_with_offset(iterations - offset));
+        let mut garden = garden(&pots, &notes)?;
+        garden.advance_to(50_000_000_000);
+        println!("Part 2: {}", Pots::from(&garden).total());
     }
 
     Ok(())
@@ -93,35 +92,14 @@ impl Pots {
     fn total(&self) -> Plant {
         self.0.iter().sum()
     }
+}
 
-    fn total_with_offset(&self, base: Plant) -> Plant {
-        self.0.iter().map(|p| p + base).sum()
-    }
-
-    fn sequence(&self) -> Vec<bool> {
-        let (first_plant, last_plant) = self.range();
-
-        (first_plant..=last_plant)
-            .map(|i| self.0.contains(&i))
-            .collect()
-    }
-
-    fn grow_once(&self, notes: &[Note]) -> Pots {
-        let mut next_generation = HashSet::new();
-
-        let (first_plant, last_plant) = self.range();
-
-        for i in (first_plant - 2)..=(last_plant + 2) {
-            for note in notes {
-                if note.grow
-                    && (-2..=2).all(|p| self.0.contains(&(i + p)) == note.plants.contains(&p))
-                {
-                    next_generation.insert(i);
-                }
-            }
-        }
-
-        Pots(next_generation)
+impl<'g, R> From<&'g LineAutomaton<bool, R>> for Pots
+where
+    R: FnMut(&[bool]) -> bool,
+{
+    fn from(garden: &'g LineAutomaton<bool, R>) -> Self {
+        Pots(garden.iter().filter(|(_, p)| **p).map(|(i, _)| i).collect())
     }
 }
 
@@ -223,18 +201,23 @@ mod test {
     #[test]
     fn example_part1() {
         let notes = example_notes();
-        let mut pots = "#..#.#..##......###...###".parse::<Pots>().unwrap();
+        let pots = "#..#.#..##......###...###".parse::<Pots>().unwrap();
+        let mut garden = garden(&pots, &notes).unwrap();
 
         for _ in 0..10 {
-            pots = pots.grow_once(&notes);
+            garden.step();
         }
 
-        assert_eq!(&format!("{}", pots), ".#.#..#...#.##....##..##..##..##..");
+        assert_eq!(
+            &format!("{}", Pots::from(&garden)),
+            ".#.#..#...#.##....##..##..##..##.."
+        );
 
         for _ in 10..20 {
-            pots = pots.grow_once(&notes);
+            garden.step();
         }
 
+        let pots = Pots::from(&garden);
         assert_eq!(
             &format!("{}", pots),
             "#....##....#####...#######....#.#..##.."
@@ -243,6 +226,13 @@ mod test {
         assert_eq!(pots.total(), 325);
     }
 
+    #[test]
+    fn empty_pots_grow() {
+        let pots = "#".parse::<Pots>().unwrap();
+        let notes = construct_notes("..... => #").unwrap();
+        assert!(garden(&pots, &notes).is_err());
+    }
+
     #[test]
     fn parse_note() {
         assert_eq!(
@@ -272,25 +262,23 @@ mod test {
 
     #[test]
     fn answer_part1() {
-        let (mut pots, notes) = get_pots().unwrap();
+        let (pots, notes) = get_pots().unwrap();
+        let mut garden = garden(&pots, &notes).unwrap();
 
         for _ in 0..20 {
-            pots = pots.grow_once(&notes);
+            garden.step();
         }
 
-        assert_eq!(pots.total(), 4200);
+        assert_eq!(Pots::from(&garden).total(), 4200);
     }
 
     #[test]
     fn answer_part2() {
         let (pots, notes) = get_pots().unwrap();
 
-        let iterations = 50_000_000_000i64;
-        let (pots, offset) = evolve_until_stable(pots, &notes, iterations);
-        assert_eq!(
-            pots.total_with_offset(iterations - offset),
-            9_699_999_999_321
-        );
+        let mut garden = garden(&pots, &notes).unwrap();
+        garden.advance_to(50_000_000_000);
+        assert_eq!(Pots::from(&garden).total(), 9_699_999_999_321);
     }
 
 }
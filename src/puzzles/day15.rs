@@ -1,8 +1,8 @@
-use failure::Error;
+use failure::{format_err, Error};
 
 use goblinwars::map::MapBuilder;
-use goblinwars::sprite::{Species, SpriteBuilder};
-use goblinwars::{Game, GameOutcome};
+use goblinwars::sprite::Species;
+use goblinwars::{FlawlessSearch, Game, GameOutcome};
 
 type Result<T> = ::std::result::Result<T, Error>;
 
@@ -22,28 +22,14 @@ pub(crate) fn main() -> Result<()> {
         println!("Part 1: {:3}", stats.score);
     }
 
-    for attack in 4.. {
-        let builder = MapBuilder::new(SpriteBuilder::default().with_attack(Species::Elf, attack));
-
-        let mut game = Game::new(builder.build(&map)?);
-
-        let n_elves = game.map().alive(Species::Elf);
-
-        let outcome = game.run(|_, t| {
-            eprint!("\r Time: {:3} ({:2})", t, attack);
-            Ok(())
-        })?;
-
-        if n_elves == game.map().alive(Species::Elf) {
-            if let GameOutcome::Complete(stats) = outcome {
-                if stats.victors == Species::Elf {
-                    eprint!("\n");
-                    println!("Part 2: {}", stats.score);
-                    break;
-                }
-            }
-        }
-    }
+    let search = FlawlessSearch::new(MapBuilder::default().build(&map)?, Species::Elf)
+        .with_range(4..=200)
+        .run();
+    let (attack, stats) = search
+        .minimum()
+        .ok_or_else(|| format_err!("No attack lets the elves win without losses"))?;
+    eprintln!("Elves need an attack of {}", attack);
+    println!("Part 2: {}", stats.score);
 
     Ok(())
 }
@@ -1,11 +1,9 @@
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
-use std::fmt;
-use std::num::ParseIntError;
-use std::str::FromStr;
-
 use failure::{Error, Fail};
 
-use crate::elfcode::{Instruction, Opcode, Register, RegisterError, Value};
+use crate::elfcode::discovery::{ParseSampleError, Sample, Solution, Solver, Unsolved};
+use crate::elfcode::encoding::{OpcodeTable, TableError};
+use crate::elfcode::program::Program;
+use crate::elfcode::{Instruction, RawInstruction, Register, RegisterError};
 
 pub(crate) fn main() -> Result<(), Error> {
     use crate::input_to_string;
@@ -22,13 +20,10 @@ pub(crate) fn main() -> Result<(), Error> {
     let mut decoder = Decoder::new();
     decoder.discover(&samples)?;
 
-    let test_program: Vec<_> = test_program
-        .into_iter()
-        .map(|i| decoder.decode(i))
-        .collect();
+    let test_program = decoder.decode(&test_program)?;
 
     let state = Register::new(4);
-    let outcome = processor(state, &test_program)?;
+    let outcome = processor(state, test_program.instructions())?;
     println!("Part 2: {}", outcome.get(0)?);
 
     Ok(())
@@ -74,290 +69,53 @@ fn samples_and_program(s: &str) -> Result<(Vec<Sample>, Vec<RawInstruction>), Pa
     Ok((samples, instructions))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct RawInstruction {
-    opcode: Value,
-    input_a: Value,
-    input_b: Value,
-    output: Value,
-}
-
-impl RawInstruction {
-    fn convert(&self, opcode: Opcode) -> Instruction {
-        Instruction::new(opcode, self.input_a, self.input_b, self.output)
-    }
-}
-
-impl fmt::Display for RawInstruction {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{} {} {} {}",
-            self.opcode, self.input_a, self.input_b, self.output
-        )
-    }
-}
-
-#[derive(Debug, Fail)]
-enum ParseRawInstructionError {
-    #[fail(display = "Invalid Value: {}", _0)]
-    InvalidValue(ParseIntError),
-
-    #[fail(display = "Invalid Opcode: {}", _0)]
-    InvalidOpcode(Value),
-}
-
-impl From<ParseIntError> for ParseRawInstructionError {
-    fn from(error: ParseIntError) -> Self {
-        ParseRawInstructionError::InvalidValue(error)
-    }
-}
-
-impl FromStr for RawInstruction {
-    type Err = ParseRawInstructionError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let values: Vec<Value> = s
-            .trim()
-            .split_whitespace()
-            .map(|i| i.trim().parse::<Value>())
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let opcode = values[0];
-        if opcode > 16 || opcode < 0 {
-            return Err(ParseRawInstructionError::InvalidOpcode(opcode));
-        }
-
-        let a = values[1];
-        let b = values[2];
-        let c = values[3];
-
-        Ok(RawInstruction {
-            opcode: opcode,
-            input_a: a,
-            input_b: b,
-            output: c,
-        })
-    }
-}
-
-#[derive(Debug, Fail)]
-pub(crate) enum ParseRegisterError {
-    #[fail(display = "Invalid Value: {}", _0)]
-    InvalidValue(ParseIntError),
-}
-
-impl From<ParseIntError> for ParseRegisterError {
-    fn from(error: ParseIntError) -> Self {
-        ParseRegisterError::InvalidValue(error)
-    }
-}
-
-impl FromStr for Register {
-    type Err = ParseRegisterError;
-
-    #[allow(deprecated)]
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let values: Vec<Value> = s
-            .trim()
-            .trim_left_matches('[')
-            .trim_right_matches(']')
-            .split(',')
-            .map(|i| i.trim().parse::<Value>())
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(Self::from(values))
-    }
-}
-
-#[derive(Debug, Clone)]
-struct Sample {
-    before: Register,
-    instruction: RawInstruction,
-    after: Register,
-}
-
-impl Sample {
-    fn evaluate(&self, opcode: Opcode) -> bool {
-        let instruction = self.instruction.convert(opcode);
-
-        let mut register = self.before.clone();
-        match instruction.process(&mut register) {
-            Ok(()) => register == self.after,
-            Err(_) => false,
-        }
-    }
-
-    fn identify(&self) -> HashSet<Opcode> {
-        let mut valid = HashSet::new();
-        for oc in &Opcode::all() {
-            if self.evaluate(*oc) {
-                valid.insert(*oc);
-            }
-        }
-        valid
-    }
-}
-
-impl fmt::Display for Sample {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "Before: {}", self.before)?;
-        writeln!(f, "{}", self.instruction)?;
-        writeln!(f, "After: {}", self.after)
-    }
-}
-
 #[derive(Debug, Fail)]
-enum ParseSampleError {
-    #[fail(display = "Register Error: {}", _0)]
-    Register(ParseRegisterError),
-
-    #[fail(display = "No before sample found: {}", _0)]
-    MissingBefore(String),
-
-    #[fail(display = "No instruction found: {}", _0)]
-    MissingInstruction(String),
-
-    #[fail(display = "Instruction Error: {}", _0)]
-    Instruction(ParseRawInstructionError),
+enum DecoderError {
+    #[fail(display = "Contradictory samples: {}", _0)]
+    Contradiction(#[cause] Unsolved),
 
-    #[fail(display = "No after sample found: {}", _0)]
-    MissingAfter(String),
-}
+    #[fail(display = "{}", _0)]
+    MultipleSolutions(#[cause] Unsolved),
 
-impl From<ParseRegisterError> for ParseSampleError {
-    fn from(error: ParseRegisterError) -> Self {
-        ParseSampleError::Register(error)
-    }
+    #[fail(display = "{}", _0)]
+    NoSolution(#[cause] Unsolved),
 }
 
-impl From<ParseRawInstructionError> for ParseSampleError {
-    fn from(error: ParseRawInstructionError) -> Self {
-        ParseSampleError::Instruction(error)
-    }
-}
-
-impl FromStr for Sample {
-    type Err = ParseSampleError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        let bline = lines
-            .next()
-            .ok_or_else(|| ParseSampleError::MissingBefore(s.to_string()))?;
-
-        if !bline.starts_with("Before: ") {
-            return Err(ParseSampleError::MissingBefore(bline.to_string()));
-        }
-
-        let before: Register = bline
-            .split(':')
-            .nth(1)
-            .ok_or_else(|| ParseSampleError::MissingBefore(bline.to_string()))?
-            .parse()?;
-
-        let instruction: RawInstruction = lines
-            .next()
-            .ok_or_else(|| ParseSampleError::MissingInstruction(s.to_string()))?
-            .parse()?;
-
-        let aline = lines
-            .next()
-            .ok_or_else(|| ParseSampleError::MissingAfter(s.to_string()))?;
-
-        if !aline.starts_with("After: ") {
-            return Err(ParseSampleError::MissingAfter(aline.to_string()));
+impl From<Unsolved> for DecoderError {
+    fn from(error: Unsolved) -> Self {
+        if !error.contradictions().is_empty() {
+            DecoderError::Contradiction(error)
+        } else if error.solutions() > 1 {
+            DecoderError::MultipleSolutions(error)
+        } else {
+            DecoderError::NoSolution(error)
         }
-
-        let after: Register = aline
-            .split(':')
-            .nth(1)
-            .ok_or_else(|| ParseSampleError::MissingAfter(aline.to_string()))?
-            .parse()?;
-
-        Ok(Sample {
-            before: before,
-            instruction: instruction,
-            after: after,
-        })
     }
 }
 
-#[derive(Debug, Fail)]
-enum DecoderError {
-    #[fail(display = "Multiple codes for Opcode: {:?}", _0)]
-    MultipleSolutions(Opcode),
-
-    #[fail(display = "Opcode has no solution: {:?}", _0)]
-    NoSolution(Value),
-}
-
 #[derive(Debug, Clone)]
 struct Decoder {
-    codes: BTreeMap<Value, Opcode>,
+    table: OpcodeTable,
 }
 
 impl Decoder {
     fn new() -> Self {
         Self {
-            codes: BTreeMap::new(),
+            table: OpcodeTable::default(),
         }
     }
 
-    fn decode(&self, instruction: RawInstruction) -> Instruction {
-        let opcode = self
-            .codes
-            .get(&instruction.opcode)
-            .expect("Invalid decoding:");
-        instruction.convert(*opcode)
+    fn decode(&self, instructions: &[RawInstruction]) -> Result<Program, TableError> {
+        self.table.decode_program(instructions, None)
     }
 
-    fn discover(&mut self, samples: &[Sample]) -> Result<(), DecoderError> {
-        let mut identifiers = BTreeMap::new();
-        let mut solution = HashMap::new();
-
-        for sample in samples {
-            let sample_candidates = sample.identify();
-            if sample_candidates.is_empty() {
-                return Err(DecoderError::NoSolution(sample.instruction.opcode));
-            }
-
-            let candidates = identifiers
-                .entry(sample.instruction.opcode)
-                .or_insert_with(|| sample_candidates.clone());
-
-            candidates.retain(|c| sample_candidates.contains(c));
-            if candidates.is_empty() {
-                return Err(DecoderError::NoSolution(sample.instruction.opcode));
-            }
-        }
-
-        let mut queue: VecDeque<Value> = identifiers.keys().cloned().collect();
-
-        while !queue.is_empty() {
-            let opcode = queue.pop_front().expect("The queue can't be empty!");
-            if identifiers[&opcode].len() == 1 {
-                let oc = *identifiers[&opcode].iter().nth(0).unwrap();
-                if solution.insert(oc, opcode).is_some() {
-                    return Err(DecoderError::MultipleSolutions(oc));
-                }
-            } else if identifiers[&opcode].is_empty() {
-                return Err(DecoderError::NoSolution(opcode));
-            } else {
-                identifiers
-                    .get_mut(&opcode)
-                    .map(|set| set.retain(|c| !solution.contains_key(c)))
-                    .ok_or_else(|| DecoderError::NoSolution(opcode))?;
-
-                queue.push_back(opcode);
-            }
+    #[allow(clippy::result_large_err)]
+    fn discover(&mut self, samples: &[Sample]) -> Result<Solution, DecoderError> {
+        let solution = Solver::new().solve(samples)?;
+        for (code, opcode) in solution.codes() {
+            self.table.insert(*code, *opcode);
         }
-
-        for (key, value) in &solution {
-            self.codes.insert(*value, *key);
-        }
-
-        Ok(())
+        Ok(solution)
     }
 }
 
@@ -374,8 +132,17 @@ mod test {
 
     use super::*;
 
+    use std::collections::BTreeMap;
+
+    use failure::{bail, ensure};
+
+    use crate::elfcode::{Opcode, Value};
+
+    use crate::elfcode::generator::{Generated, Generator};
+
     #[test]
     fn example_part1() {
+        use std::collections::BTreeSet;
         use std::iter::FromIterator;
 
         let sample: Sample = "Before: [3, 2, 1, 1]
@@ -386,8 +153,8 @@ After:  [3, 2, 2, 1]"
 
         assert_eq!(sample.identify().len(), 3);
 
-        let opcodes: HashSet<Opcode> =
-            HashSet::from_iter([Opcode::Mulr, Opcode::Addi, Opcode::Seti].iter().cloned());
+        let opcodes: BTreeSet<Opcode> =
+            BTreeSet::from_iter([Opcode::Mulr, Opcode::Addi, Opcode::Seti].iter().cloned());
 
         assert_eq!(sample.identify(), opcodes);
     }
@@ -428,15 +195,111 @@ After:  [3, 2, 2, 1]"
         let mut decoder = Decoder::new();
         decoder.discover(&samples).unwrap();
 
-        let test_program: Vec<_> = test_program
-            .into_iter()
-            .map(|i| decoder.decode(i))
-            .collect();
+        let test_program = decoder.decode(&test_program).unwrap();
 
         let state = Register::new(4);
-        let outcome = processor(state, &test_program).unwrap();
+        let outcome = processor(state, test_program.instructions()).unwrap();
 
         assert_eq!(outcome.get(0).unwrap(), 554);
     }
 
+    /// The outcome of checking the decoder against a generated puzzle.
+    #[derive(Debug, PartialEq)]
+    enum SelfCheck {
+        /// The hidden permutation and the program's result were recovered.
+        Recovered,
+
+        /// The samples didn't pin down every opcode, and the decoder said so.
+        Ambiguous,
+    }
+
+    fn self_check(generated: &Generated) -> Result<SelfCheck, Error> {
+        let (samples, program) = samples_and_program(&generated.to_string())?;
+        ensure!(samples == generated.samples(), "Samples didn't round trip");
+        ensure!(program == generated.program(), "Program didn't round trip");
+
+        let mut decoder = Decoder::new();
+        match decoder.discover(&samples) {
+            Ok(_) => {}
+            Err(DecoderError::MultipleSolutions(unsolved)) => {
+                for (code, candidates) in &unsolved.ambiguous() {
+                    ensure!(
+                        candidates.contains(&generated.codes()[code]),
+                        "Decoder ruled out {:?} for {}",
+                        generated.codes()[code],
+                        code
+                    );
+                }
+                return Ok(SelfCheck::Ambiguous);
+            }
+            Err(error) => bail!("Decoder failed on a solvable puzzle: {}", error),
+        }
+
+        ensure!(
+            decoder.table == OpcodeTable::from(generated.codes().clone()),
+            "Decoded {:?}, expected {:?}",
+            decoder.table,
+            generated.codes()
+        );
+
+        let program = decoder.decode(&program)?;
+        let outcome = processor(Register::new(4), program.instructions())?;
+        ensure!(
+            &outcome == generated.result(),
+            "Program finished with {}, expected {}",
+            outcome,
+            generated.result()
+        );
+
+        Ok(SelfCheck::Recovered)
+    }
+
+    #[test]
+    fn generated_puzzles() {
+        for seed in 0..20 {
+            let generated = Generator::new(seed).generate(20, 50).unwrap();
+            assert_eq!(self_check(&generated).unwrap(), SelfCheck::Recovered);
+        }
+    }
+
+    #[test]
+    fn chosen_puzzle() {
+        let codes: BTreeMap<Value, Opcode> = Opcode::all()
+            .into_iter()
+            .rev()
+            .enumerate()
+            .map(|(i, oc)| (i as Value, oc))
+            .collect();
+        let program: Vec<Instruction> = ["seti 3 0 0", "addi 0 4 1", "mulr 0 1 2", "gtri 2 20 3"]
+            .iter()
+            .map(|line| line.parse().unwrap())
+            .collect();
+
+        let generated = Generator::new(7)
+            .generate_from(codes.clone(), &program, 20)
+            .unwrap();
+        assert_eq!(generated.codes(), &codes);
+        assert_eq!(generated.result(), &Register::from(vec![3, 7, 21, 1]));
+        assert_eq!(self_check(&generated).unwrap(), SelfCheck::Recovered);
+
+        let mut squares = vec!["mulr 0 0 0".parse::<Instruction>().unwrap(); 8];
+        squares[0] = "seti 3 0 0".parse().unwrap();
+        assert!(Generator::new(7)
+            .generate_from(codes.clone(), &squares, 1)
+            .is_err());
+
+        let mut partial = codes;
+        partial.retain(|_, oc| *oc != Opcode::Seti);
+        assert!(Generator::new(7)
+            .generate_from(partial, &program, 1)
+            .is_err());
+    }
+
+    #[test]
+    fn generated_ambiguous_puzzles() {
+        let outcomes = (0..50)
+            .map(|seed| self_check(&Generator::new(seed).generate(1, 10).unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert!(outcomes.contains(&SelfCheck::Ambiguous));
+    }
 }
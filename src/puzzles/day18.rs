@@ -1,13 +1,11 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::fmt;
-use std::mem;
 use std::str::FromStr;
 
-use failure::{format_err, Error, Fail};
+use failure::{Error, Fail};
 
-use geometry::{BoundingBox, Point};
-
-use crate::iterhelper::repeated_element;
+use geometry::{Automaton, BoundingBox, Grid, MapParser, ParseMapError, Point};
 
 pub(crate) fn main() -> Result<(), Error> {
     use crate::input_to_string;
@@ -25,16 +23,9 @@ pub(crate) fn main() -> Result<(), Error> {
 }
 
 fn part2(lumber: LumberArea) -> Result<usize, Error> {
-    let target = 1_000_000_000;
-    let pattern = repeated_element(lumber.clone().evolve().map(|l| l.to_string()))
-        .ok_or_else(|| format_err!("No pattern found."))?;
-    let offset = (target - pattern.start()) % pattern.length();
-    Ok(lumber
-        .evolve()
-        .skip(pattern.start())
-        .nth(offset)
-        .unwrap()
-        .resource_value())
+    let mut forest = lumber.forest();
+    forest.advance_to(1_000_000_000);
+    Ok(LumberArea::from(forest.grid()).resource_value())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
@@ -60,6 +51,19 @@ enum ParseAcreError {
     Unknown(String),
 }
 
+impl TryFrom<char> for Acre {
+    type Error = ParseAcreError;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '.' => Ok(Acre::OpenGround),
+            '#' => Ok(Acre::Lumberyard),
+            '|' => Ok(Acre::Trees),
+            _ => Err(ParseAcreError::Unknown(c.to_string())),
+        }
+    }
+}
+
 impl FromStr for Acre {
     type Err = ParseAcreError;
 
@@ -125,65 +129,65 @@ impl LumberArea {
     }
 
     fn evolve(self) -> LumberIterator {
-        LumberIterator { area: self }
+        LumberIterator {
+            forest: self.forest(),
+        }
     }
 
-    fn change(&self, point: Point) -> Acre {
-        let a = self.get(point);
-        match a {
-            Acre::OpenGround => {
-                if point
-                    .adjacent_diagonal()
-                    .filter(|p| self.get(*p) == Acre::Trees)
-                    .count()
-                    >= 3
-                {
-                    Acre::Trees
-                } else {
-                    Acre::OpenGround
-                }
+    fn grid(&self) -> Grid<Acre> {
+        Grid::from_fn(self.bbox, |point| self.get(point))
+    }
+
+    fn forest(&self) -> Forest {
+        Automaton::new(self.grid(), Acre::OpenGround, change)
+    }
+}
+
+type Forest = Automaton<Acre, fn(&Acre, &[Acre]) -> Acre>;
+
+fn change(acre: &Acre, neighbours: &[Acre]) -> Acre {
+    let count = |kind| neighbours.iter().filter(|a| **a == kind).count();
+    match acre {
+        Acre::OpenGround => {
+            if count(Acre::Trees) >= 3 {
+                Acre::Trees
+            } else {
+                Acre::OpenGround
             }
-            Acre::Trees => {
-                if point
-                    .adjacent_diagonal()
-                    .filter(|p| self.get(*p) == Acre::Lumberyard)
-                    .count()
-                    >= 3
-                {
-                    Acre::Lumberyard
-                } else {
-                    Acre::Trees
-                }
+        }
+        Acre::Trees => {
+            if count(Acre::Lumberyard) >= 3 {
+                Acre::Lumberyard
+            } else {
+                Acre::Trees
             }
-            Acre::Lumberyard => {
-                let neighbors: HashSet<Acre> =
-                    point.adjacent_diagonal().map(|p| self.get(p)).collect();
-                if neighbors.contains(&Acre::Lumberyard) && neighbors.contains(&Acre::Trees) {
-                    Acre::Lumberyard
-                } else {
-                    Acre::OpenGround
-                }
+        }
+        Acre::Lumberyard => {
+            if count(Acre::Lumberyard) > 0 && count(Acre::Trees) > 0 {
+                Acre::Lumberyard
+            } else {
+                Acre::OpenGround
             }
         }
     }
 }
 
-impl FromStr for LumberArea {
-    type Err = ParseAcreError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl<'g> From<&'g Grid<Acre>> for LumberArea {
+    fn from(grid: &'g Grid<Acre>) -> Self {
         let mut zone = LumberArea::empty();
-
-        for (y, line) in s.lines().enumerate().take(50) {
-            for (x, c) in line.chars().enumerate().take(50) {
-                zone.insert(
-                    Point::new(x as i32, y as i32),
-                    c.to_string().parse::<Acre>()?,
-                );
-            }
+        for (point, acre) in grid.iter() {
+            zone.insert(point, *acre);
         }
+        zone
+    }
+}
 
-        Ok(zone)
+impl FromStr for LumberArea {
+    type Err = ParseMapError<ParseAcreError>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid = MapParser::new().with_limit(50, 50).parse::<Acre>(s)?;
+        Ok(LumberArea::from(&grid))
     }
 }
 
@@ -201,24 +205,16 @@ impl fmt::Display for LumberArea {
 }
 
 struct LumberIterator {
-    area: LumberArea,
+    forest: Forest,
 }
 
 impl Iterator for LumberIterator {
     type Item = LumberArea;
 
     fn next(&mut self) -> Option<LumberArea> {
-        let bbox = self.area.bbox();
-        let mut area = LumberArea::with_bbox(bbox);
-
-        for y in bbox.vertical() {
-            for x in bbox.horizontal() {
-                let point = Point::new(x, y);
-                area.insert(point, self.area.change(point));
-            }
-        }
-
-        Some(mem::replace(&mut self.area, area))
+        let area = LumberArea::from(self.forest.grid());
+        self.forest.step();
+        Some(area)
     }
 }
 
@@ -233,6 +229,22 @@ mod test {
         assert_eq!("|".parse::<Acre>().unwrap(), Acre::Trees);
     }
 
+    #[test]
+    fn lumber_area_limits() {
+        let row = ".".repeat(51);
+        match row.parse::<LumberArea>() {
+            Err(ParseMapError::TooLarge(51, 1, 50, 50)) => {}
+            other => panic!("Expected an oversized map, got {:?}", other),
+        }
+
+        match ".#\n|x".parse::<LumberArea>() {
+            Err(ParseMapError::Cell {
+                line: 2, column: 2, ..
+            }) => {}
+            other => panic!("Expected an invalid acre, got {:?}", other),
+        }
+    }
+
     #[test]
     fn lumber_area() {
         let la: LumberArea = ".#\n|.".parse().unwrap();
@@ -1,37 +1,48 @@
 use failure::{format_err, Error};
-use std::io::prelude::*;
 
-use crate::elfcode::psuedocoder::decompile;
-use crate::elfcode::{Instruction, InstructionPointer, Processor};
+use crate::elfcode::monitor::Watch;
+use crate::elfcode::program::Program;
+use crate::elfcode::psuedocoder::{decompile, RegisterNames};
+use crate::elfcode::ProcessorBuilder;
 
 pub(crate) fn main() -> Result<(), Error> {
-    use crate::input;
+    use crate::input_to_string;
 
-    let mut lines = input(19)?.lines();
+    let program: Program = input_to_string(19)?.parse()?;
+    let mut processor = ProcessorBuilder::from_program(&program).build()?;
 
-    let ip = lines
-        .nth(0)
-        .ok_or_else(|| format_err!("No instruction pointer found"))?
-        .map_err(Error::from)
-        .and_then(|l| l.parse::<InstructionPointer>().map_err(Error::from))?;
-
-    let program = lines
-        .map(|lr| {
-            lr.map_err(Error::from)
-                .and_then(|l| l.parse::<Instruction>().map_err(Error::from))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-
-    let mut processor = Processor::new(program.clone(), 6, ip.into());
-
-    let r = processor
-        .run()
+    let mut run = processor.run();
+    let r = run
+        .by_ref()
         .last()
         .ok_or_else(|| format_err!("No steps ran!"))?;
+    if let Some(error) = run.error() {
+        return Err(format_err!("Program stopped: {}", error));
+    }
 
     println!("Part 1: {}", r.get(0)?);
 
-    eprintln!("{}", decompile(&program, ip.into()));
+    let ip = program
+        .ip()
+        .ok_or_else(|| format_err!("No instruction pointer found"))?;
+
+    // The setup at L17 leaves a number in register 2, and the loops
+    // from L1 add each of its divisors to register 0.
+    let names = RegisterNames::new(ip)
+        .with_name(0, "sum")
+        .with_name(2, "target");
+    eprintln!("{}", decompile(program.instructions(), &names));
+
+    // Part 2 starts with register 0 set to 1, so the setup builds a much
+    // larger target, then clears register 0 before the loops start.
+    let mut processor = ProcessorBuilder::from_program(&program)
+        .with_register(0, 1)
+        .build()?;
+    let setup = processor
+        .monitor(Watch::changed(0))
+        .next()
+        .ok_or_else(|| format_err!("Part 2 setup never finished"))?;
+    eprintln!("Part 2 sums the divisors of {}", setup.register().get(2)?);
 
     Ok(())
 }
@@ -40,22 +51,21 @@ pub(crate) fn main() -> Result<(), Error> {
 mod test {
     use super::*;
 
-    use crate::elfcode::Register;
+    use crate::elfcode::{Processor, Register};
 
     fn example_program() -> Processor {
-        let commands = "seti 5 0 1
+        let program: Program = "#ip 0
+seti 5 0 1
 seti 6 0 2
 addi 0 1 0
 addr 1 2 3
 setr 1 0 0
 seti 8 0 4
 seti 9 0 5"
-            .lines()
-            .map(|l| l.parse::<Instruction>().unwrap())
-            .collect::<Vec<_>>();
+            .parse()
+            .unwrap();
 
-        let ip = "#ip 0".parse::<InstructionPointer>().unwrap();
-        Processor::new(commands, 6, ip.into())
+        ProcessorBuilder::from_program(&program).build().unwrap()
     }
 
     #[test]
@@ -68,4 +78,18 @@ seti 9 0 5"
         assert_eq!(r, Register::from(vec![7, 5, 6, 0, 0, 9]));
     }
 
+    #[test]
+    fn part2_setup() {
+        use crate::input_to_string;
+
+        let program: Program = input_to_string(19).unwrap().parse().unwrap();
+        let mut p = ProcessorBuilder::from_program(&program)
+            .with_register(0, 1)
+            .build()
+            .unwrap();
+
+        let setup = p.monitor(Watch::changed(0)).next().unwrap();
+        assert_eq!(setup.register().get(0).unwrap(), 0);
+        assert_eq!(setup.register().get(2).unwrap(), 10_551_287);
+    }
 }
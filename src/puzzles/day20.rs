@@ -1,10 +1,9 @@
 use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
-use std::str::FromStr;
 
 use failure::{format_err, Error, Fail};
 
-use geometry::{self, Point};
+use geometry::{Compass, ParseCompassError, Point};
 
 pub(crate) fn main() -> Result<(), Error> {
     use crate::input_to_string;
@@ -26,56 +25,6 @@ pub(crate) fn main() -> Result<(), Error> {
     Ok(())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-impl From<geometry::Direction> for Direction {
-    fn from(gd: geometry::Direction) -> Self {
-        match gd {
-            geometry::Direction::Up => Direction::North,
-            geometry::Direction::Down => Direction::South,
-            geometry::Direction::Left => Direction::West,
-            geometry::Direction::Right => Direction::East,
-        }
-    }
-}
-
-impl From<Direction> for geometry::Direction {
-    fn from(d: Direction) -> Self {
-        match d {
-            Direction::North => geometry::Direction::Up,
-            Direction::South => geometry::Direction::Down,
-            Direction::East => geometry::Direction::Right,
-            Direction::West => geometry::Direction::Left,
-        }
-    }
-}
-
-#[derive(Debug, Fail)]
-enum ParseDirectionError {
-    #[fail(display = "Invalid direction {}", _0)]
-    InvalidDirection(String),
-}
-
-impl FromStr for Direction {
-    type Err = ParseDirectionError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "N" => Ok(Direction::North),
-            "S" => Ok(Direction::South),
-            "E" => Ok(Direction::East),
-            "W" => Ok(Direction::West),
-            _ => Err(ParseDirectionError::InvalidDirection(s.to_string())),
-        }
-    }
-}
-
 #[derive(Debug, Clone)]
 struct Room {
     distance: usize,
@@ -108,8 +57,8 @@ impl PathFragment {
         Self::new(Point::new(0, 0), Room::new(0))
     }
 
-    fn step(&self, direction: Direction) -> Self {
-        Self::new(self.position.step(direction.into()), self.room.step())
+    fn step(&self, heading: Compass) -> Self {
+        Self::new(self.position.step_compass(heading), self.room.step())
     }
 }
 
@@ -179,11 +128,11 @@ enum ParseError {
     UnbalancedParens,
 
     #[fail(display = "Invalid Direction: {}", _0)]
-    InvalidDirection(ParseDirectionError),
+    InvalidDirection(ParseCompassError),
 }
 
-impl From<ParseDirectionError> for ParseError {
-    fn from(error: ParseDirectionError) -> Self {
+impl From<ParseCompassError> for ParseError {
+    fn from(error: ParseCompassError) -> Self {
         ParseError::InvalidDirection(error)
     }
 }
@@ -211,7 +160,7 @@ fn parse_regex(pattern: &str) -> Result<Map, ParseError> {
                 position = stack.peek().ok_or(ParseError::UnbalancedParens)?.clone();
             }
             _ => {
-                position = position.step(format!("{}", c).parse::<Direction>()?);
+                position = position.step(format!("{}", c).parse::<Compass>()?);
                 map.insert(&position);
             }
         };
@@ -226,7 +175,7 @@ mod test {
 
     #[test]
     fn direction() {
-        assert_eq!("N".parse::<Direction>().unwrap(), Direction::North);
+        assert_eq!("N".parse::<Compass>().unwrap(), Compass::North);
     }
 
     #[test]
@@ -1,43 +1,51 @@
 use failure::{format_err, Error};
-use std::io::prelude::*;
 
-use crate::elfcode::{Instruction, InstructionPointer, Processor};
+use crate::elfcode::monitor::Watch;
+use crate::elfcode::program::Program;
+use crate::elfcode::{Arithmetic, Opcode, ProcessorBuilder};
 use crate::iterhelper::repeated_element;
 
 pub(crate) fn main() -> Result<(), Error> {
-    use crate::input;
+    use crate::input_to_string;
 
-    let mut lines = input(21)?.lines();
+    let program: Program = input_to_string(21)?.parse()?;
+    // The hash loop multiplies before masking, which should wrap rather
+    // than stop the program if it ever overflows.
+    let builder = ProcessorBuilder::from_program(&program).with_arithmetic(Arithmetic::Wrapping);
 
-    let ip = lines
-        .nth(0)
-        .ok_or_else(|| format_err!("No instruction pointer found"))?
-        .map_err(Error::from)
-        .and_then(|l| l.parse::<InstructionPointer>().map_err(Error::from))?;
+    // The program halts when a computed value equals register 0,
+    // so find the equality check which reads register 0 and the register
+    // it is compared against.
+    let mut processor = builder.build()?;
 
-    let program = lines
-        .map(|lr| {
-            lr.map_err(Error::from)
-                .and_then(|l| l.parse::<Instruction>().map_err(Error::from))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let equality = Watch::predicate(|_, instruction, _| instruction.opcode() == Opcode::Eqrr);
+    let mut monitor = processor.monitor(Watch::reads(0).and(equality));
+    let check = match (monitor.next(), monitor.error()) {
+        (Some(check), _) => check,
+        (None, Some(error)) => return Err(format_err!("Program stopped: {}", error)),
+        (None, None) => return Err(format_err!("Register 0 is never checked")),
+    };
 
-    let mut processor = Processor::new(program.clone(), 6, ip.into());
+    let register = check
+        .instruction()
+        .reads()
+        .into_iter()
+        .find(|r| *r != 0)
+        .ok_or_else(|| format_err!("No register compared with register 0"))?;
 
-    let r = processor
-        .monitor_instruction(28)
-        .nth(0)
-        .ok_or_else(|| format_err!("No steps ran!"))?;
+    println!("Part 1: {}", check.register().get(register)?);
 
-    println!("Part 1: {}", r.get(4)?);
-
-    let mut processor = Processor::new(program.clone(), 6, ip.into());
+    let mut processor = builder.build()?;
 
     println!(
         "Part 2: {}",
-        repeated_element(processor.monitor_instruction(28).map(|r| r.get(4).unwrap()))
-            .ok_or_else(|| format_err!("No pattern found."))?
-            .last()
+        repeated_element(
+            processor
+                .monitor(Watch::ip(check.ip()))
+                .map(|h| h.register().get(register).unwrap())
+        )
+        .ok_or_else(|| format_err!("No pattern found."))?
+        .last()
     );
 
     Ok(())
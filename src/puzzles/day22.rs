@@ -1,11 +1,11 @@
 use std::cell::RefCell;
-use std::cmp;
-use std::collections::{BTreeMap, BinaryHeap};
+use std::collections::BTreeMap;
 use std::num::ParseIntError;
 use std::str::FromStr;
 
 use failure::{format_err, Error, Fail};
 
+use geometry::search;
 use geometry::{BoundingBox, Direction, ParsePointError, Point, Position};
 
 pub(crate) fn main() -> Result<(), Error> {
@@ -207,122 +207,33 @@ impl Equipment {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct PathFragment {
-    position: Point,
-    distance: usize,
-    heuristic: usize,
-    equipment: Equipment,
-}
-
-impl PathFragment {
-    fn new(position: Point, distance: usize, heuristic: usize, equipment: Equipment) -> Self {
-        Self {
-            position,
-            distance,
-            heuristic,
-            equipment,
-        }
-    }
-}
-
-impl cmp::Ord for PathFragment {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        self.heuristic
-            .cmp(&other.heuristic)
-            .reverse()
-            .then(self.position.cmp(&other.position))
-    }
-}
-
-impl cmp::PartialOrd for PathFragment {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-#[derive(Debug)]
-struct PathQueue {
-    queue: BinaryHeap<PathFragment>,
-    seen: BTreeMap<(Point, Equipment), usize>,
-}
-
-impl PathQueue {
-    fn new() -> Self {
-        Self {
-            queue: BinaryHeap::new(),
-            seen: BTreeMap::new(),
-        }
-    }
-
-    fn push(&mut self, path: PathFragment) {
-        match self.seen.get_mut(&(path.position, path.equipment)) {
-            Some(d) => {
-                if *d > path.distance {
-                    *d = path.distance;
-                    self.queue.push(path);
-                }
-            }
-            None => {
-                self.seen
-                    .insert((path.position, path.equipment), path.distance);
-                self.queue.push(path);
-            }
-        }
-    }
-
-    fn pop(&mut self) -> Option<PathFragment> {
-        self.queue.pop()
-    }
-}
-
 fn shortest_path(cave: &Cave, origin: Point) -> Option<usize> {
-    let path = PathFragment::new(
-        origin,
-        0,
-        origin.manhattan_distance(cave.target) as usize,
-        Equipment::Torch,
-    );
-    let mut queue = PathQueue::new();
-
-    queue.push(path);
+    let target = (cave.target, Equipment::Torch);
 
-    while let Some(path) = queue.pop() {
-        if path.position == cave.target && path.equipment == Equipment::Torch {
-            return Some(path.distance);
-        }
+    let successors = |&(position, equipment): &(Point, Equipment)| {
+        let current_typ = cave.region(position).typ();
+        let mut next = vec![((position, equipment.change_gear(current_typ)), 7)];
 
-        let current_typ = cave.region(path.position).typ();
-
-        let change_equipment_path = PathFragment::new(
-            path.position,
-            path.distance + 7,
-            path.heuristic + 7,
-            path.equipment.change_gear(current_typ),
-        );
-        queue.push(change_equipment_path);
-
-        for adjacent in path.position.adjacent() {
+        for adjacent in position.adjacent() {
             if adjacent.x < 0 || adjacent.y < 0 {
                 continue;
             }
 
-            let typ = cave.region(adjacent).typ();
-
-            if path.equipment.passable(typ) {
-                let adjacent_path = PathFragment::new(
-                    adjacent,
-                    path.distance + 1,
-                    path.distance + 1 + adjacent.manhattan_distance(cave.target) as usize,
-                    path.equipment,
-                );
-                queue.push(adjacent_path);
+            if equipment.passable(cave.region(adjacent).typ()) {
+                next.push(((adjacent, equipment), 1));
             }
         }
-    }
+        next
+    };
+
+    let path = search::astar(
+        (origin, Equipment::Torch),
+        successors,
+        |(position, _)| position.manhattan_distance(cave.target) as usize,
+        |state| *state == target,
+    )?;
 
-    // No appropriate path found.
-    None
+    Some(path.cost())
 }
 
 #[cfg(test)]
@@ -1,27 +1,44 @@
 use std::num::ParseIntError;
 use std::str::FromStr;
 
-use failure::{Error, Fail};
+use failure::{format_err, Error, Fail};
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use geometry::ball::max_overlap;
+use geometry::{Ball, Coordinates, KDTree, ParsePointError, Point3D, Position};
+
 pub(crate) fn main() -> Result<(), Error> {
+    use crate::input_to_string;
+
+    let nanobots = input_to_string(23)?
+        .lines()
+        .map(|l| l.parse::<Nanobot>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    println!(
+        "Part 1: {}",
+        in_range_of_strongest(&nanobots).ok_or_else(|| format_err!("No nanobots found"))?
+    );
+    println!(
+        "Part 2: {}",
+        best_distance(&nanobots).ok_or_else(|| format_err!("No nanobots found"))?
+    );
+
     Ok(())
 }
 
-type Position = i32;
-
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct Point3D {
-    x: Position,
-    y: Position,
-    z: Position,
+fn in_range_of_strongest(nanobots: &[Nanobot]) -> Option<usize> {
+    let strongest = nanobots.iter().max_by_key(|n| n.r)?;
+    let tree = KDTree::from_points(nanobots.iter().map(|n| n.location));
+    Some(tree.within(strongest.location, strongest.r).len())
 }
 
-impl Point3D {
-    fn distance(&self, other: &Self) -> Position {
-        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
-    }
+/// The distance from the origin to the nearest point in range of the
+/// most nanobots.
+fn best_distance(nanobots: &[Nanobot]) -> Option<Position> {
+    let balls: Vec<_> = nanobots.iter().map(Nanobot::ball).collect();
+    max_overlap(&balls, Point3D::origin()).map(|o| o.distance())
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -31,8 +48,13 @@ struct Nanobot {
 }
 
 impl Nanobot {
+    #[cfg(test)]
     fn distance(&self, other: &Self) -> Position {
-        self.location.distance(&other.location)
+        self.location.manhattan_distance(other.location)
+    }
+
+    fn ball(&self) -> Ball<Point3D> {
+        Ball::new(self.location, self.r)
     }
 }
 
@@ -43,6 +65,15 @@ enum ParseNanobotError {
 
     #[fail(display = "Invalid number")]
     InvalidNumber,
+
+    #[fail(display = "Invalid position: {}", _0)]
+    InvalidPosition(#[cause] ParsePointError),
+}
+
+impl From<ParsePointError> for ParseNanobotError {
+    fn from(error: ParsePointError) -> Self {
+        ParseNanobotError::InvalidPosition(error)
+    }
 }
 
 impl From<ParseIntError> for ParseNanobotError {
@@ -56,8 +87,7 @@ impl FromStr for Nanobot {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         lazy_static! {
-            static ref RE: Regex =
-                Regex::new(r"pos=<(?P<x>\d+),(?P<y>\d+),(?P<z>\d+)>,\s+r=(?P<r>\d+)").unwrap();
+            static ref RE: Regex = Regex::new(r"pos=(?P<pos><[^>]*>),\s+r=(?P<r>\d+)").unwrap();
         }
 
         let cap = match RE.captures(s) {
@@ -65,67 +95,13 @@ impl FromStr for Nanobot {
             None => return Err(ParseNanobotError::PatternMatchFailed(s.to_string())),
         };
 
-        let location = Point3D {
-            x: cap["x"].parse()?,
-            y: cap["y"].parse()?,
-            z: cap["z"].parse()?,
-        };
-
         Ok(Self {
-            location,
+            location: cap["pos"].parse()?,
             r: cap["r"].parse()?,
         })
     }
 }
 
-#[derive(Debug)]
-struct KDNode {
-    location: Nanobot,
-    left: Option<Box<KDNode>>,
-    right: Option<Box<KDNode>>,
-}
-
-impl KDNode {
-    fn build(points: &[&Nanobot], depth: usize) -> KDNode {
-        // Find the midpoint of the elements
-        let mut points: Vec<&Nanobot> = points.to_vec();
-        points.sort_by_key(|&p| match depth % 3 {
-            0 => p.location.x,
-            1 => p.location.y,
-            2 => p.location.z,
-            _ => unreachable!(),
-        });
-
-        let midpoint = points.len() / 2;
-        let location = points[midpoint];
-
-        // Make the child nodes
-        let left = if midpoint > 0 {
-            Some(Box::from(KDNode::build(&points[0..midpoint], depth + 1)))
-        } else {
-            None
-        };
-
-        let right = if midpoint < points.len() {
-            Some(Box::from(KDNode::build(&points[midpoint..], depth + 1)))
-        } else {
-            None
-        };
-
-        // Make the node.
-        KDNode {
-            location: location.clone(),
-            left,
-            right,
-        }
-    }
-}
-
-#[derive(Debug)]
-struct KDTree {
-    root: KDNode,
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -136,5 +112,42 @@ mod test {
         let nb: Nanobot = "pos=<1,0,0>, r=1".parse().unwrap();
 
         assert_eq!(na.distance(&nb), 1);
+
+        let nc: Nanobot = "pos=<-1,2,-3>, r=1".parse().unwrap();
+        assert_eq!(nc.location, Point3D::new(-1, 2, -3));
+        assert_eq!(na.distance(&nc), 6);
+    }
+
+    #[test]
+    fn example_part1() {
+        let nanobots = "pos=<0,0,0>, r=4
+pos=<1,0,0>, r=1
+pos=<4,0,0>, r=3
+pos=<0,2,0>, r=1
+pos=<0,5,0>, r=3
+pos=<0,0,3>, r=1
+pos=<1,1,1>, r=1
+pos=<1,1,2>, r=1
+pos=<1,3,1>, r=1"
+            .lines()
+            .map(|l| l.parse::<Nanobot>().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(in_range_of_strongest(&nanobots), Some(7));
+    }
+
+    #[test]
+    fn example_part2() {
+        let nanobots = "pos=<10,12,12>, r=2
+pos=<12,14,12>, r=2
+pos=<16,12,12>, r=4
+pos=<14,14,14>, r=6
+pos=<50,50,50>, r=200
+pos=<10,10,10>, r=5"
+            .lines()
+            .map(|l| l.parse::<Nanobot>().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(best_distance(&nanobots), Some(36));
     }
 }
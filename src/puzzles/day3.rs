@@ -1,8 +1,8 @@
-use std::collections::HashMap;
 use std::io::BufRead;
 use std::str::FromStr;
 
 use failure::{format_err, Error};
+use geometry::{BoundingBox, Position, RectangleSet};
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -12,33 +12,22 @@ macro_rules! err {
     ($($tt:tt)*) => { Err(format_err!($($tt)*)) }
 }
 
-type Grid = HashMap<(Coordinate, Coordinate), usize>;
-
-fn grid<'c, C>(claims: C) -> Grid
+fn fabric<'c, C>(claims: C) -> RectangleSet
 where
     C: Iterator<Item = &'c Claim>,
 {
-    let mut grid = Grid::new();
-
-    for claim in claims {
-        for point in claim.points() {
-            *grid.entry(point).or_insert(0) += 1;
-        }
-    }
-    grid
+    claims.map(Claim::bbox).collect()
 }
 
-fn overlap(grid: &Grid) -> usize {
-    grid.values().filter(|&&c| c > 1).count()
+fn overlap(fabric: &RectangleSet) -> u64 {
+    fabric.overlap_area()
 }
 
-fn no_overlap(claims: &[Claim], grid: &Grid) -> Result<usize, Error> {
-    for claim in claims {
-        if claim.points().all(|ref p| grid[p] == 1) {
-            return Ok(claim.id);
-        }
+fn no_overlap(claims: &[Claim], fabric: &RectangleSet) -> Result<usize, Error> {
+    match fabric.isolated().first() {
+        Some(&i) => Ok(claims[i].id),
+        None => err!("No claim has zero overlaps!"),
     }
-    err!("No claim has zero overlaps!")
 }
 
 pub(crate) fn main() -> Result<(), Error> {
@@ -51,10 +40,10 @@ pub(crate) fn main() -> Result<(), Error> {
                 .and_then(|l| l.parse::<Claim>())
         })
         .collect::<Result<Vec<Claim>, _>>()?;
-    let grid = grid(claims.iter());
+    let fabric = fabric(claims.iter());
 
-    println!("Part 1: {}", overlap(&grid));
-    println!("Part 2: #{}", no_overlap(&claims, &grid)?);
+    println!("Part 1: {}", overlap(&fabric));
+    println!("Part 2: #{}", no_overlap(&claims, &fabric)?);
 
     Ok(())
 }
@@ -69,37 +58,9 @@ struct Claim {
 }
 
 impl Claim {
-    fn points(&self) -> ClaimPoints {
-        ClaimPoints {
-            claim: self,
-            px: 0,
-            py: 0,
-        }
-    }
-}
-
-struct ClaimPoints<'c> {
-    claim: &'c Claim,
-    px: Coordinate,
-    py: Coordinate,
-}
-
-impl<'c> Iterator for ClaimPoints<'c> {
-    type Item = (Coordinate, Coordinate);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.px == self.claim.w {
-            self.px = 0;
-            self.py += 1;
-        }
-
-        if self.py >= self.claim.h {
-            return None;
-        }
-
-        let result = Some((self.px + self.claim.x, self.py + self.claim.y));
-        self.px += 1;
-        result
+    fn bbox(&self) -> BoundingBox {
+        let (x, y) = (self.x as Position, self.y as Position);
+        BoundingBox::new(x, x + self.w as Position - 1, y, y + self.h as Position - 1)
     }
 }
 
@@ -158,7 +119,11 @@ mod test {
             }
         );
 
-        let points: Vec<(Coordinate, Coordinate)> = claim.points().collect();
+        let points: Vec<(Coordinate, Coordinate)> = claim
+            .bbox()
+            .points()
+            .map(|p| (p.x as Coordinate, p.y as Coordinate))
+            .collect();
 
         assert_eq!(
             points,
@@ -197,8 +162,9 @@ mod test {
             .collect::<Result<Vec<_>, _>>()
             .unwrap();
 
-        let grid = grid(claims.iter());
+        let fabric = fabric(claims.iter());
 
-        assert_eq!(overlap(&grid), 4);
+        assert_eq!(overlap(&fabric), 4);
+        assert_eq!(no_overlap(&claims, &fabric).unwrap(), 3);
     }
 }
@@ -1,8 +1,9 @@
-use std::collections::{HashMap, HashSet};
 use std::io::BufRead;
 use std::str::FromStr;
 
 use geometry;
+use geometry::voronoi::total_distance_region;
+use geometry::{Metric, Voronoi};
 
 use failure::{format_err, Error};
 use lazy_static::lazy_static;
@@ -44,12 +45,6 @@ impl FromStr for Point {
     }
 }
 
-impl Point {
-    fn distance(self, other: Point) -> i32 {
-        self.0.manhattan_distance(other.0)
-    }
-}
-
 impl From<Point> for geometry::Point {
     fn from(p: Point) -> Self {
         p.0
@@ -62,85 +57,32 @@ impl From<geometry::Point> for Point {
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct Grid {
-    bbox: geometry::BoundingBox,
-    counter: HashMap<Point, i32>,
-    infinite: HashSet<Point>,
-}
-
-impl Grid {
-    fn from_bbox(bbox: geometry::BoundingBox) -> Self {
-        Self {
-            bbox: bbox,
-            counter: HashMap::new(),
-            infinite: HashSet::new(),
-        }
-    }
-
-    fn check(&mut self, location: Point, points: &[Point]) {
-        if let Some(pt) = closest(location, points) {
-            *self.counter.entry(pt).or_insert(0) += 1;
-
-            if self.bbox.is_edge(location.into()) {
-                self.infinite.insert(pt);
-            }
-        }
-    }
-
-    fn largest_area(&self) -> i32 {
-        *self
-            .counter
-            .iter()
-            .filter(|(p, _)| !self.infinite.contains(&p))
-            .map(|(_, c)| c)
-            .max()
-            .unwrap()
-    }
+fn seeds(points: &[Point]) -> Vec<geometry::Point> {
+    points.iter().map(|&p| p.into()).collect()
 }
 
 fn vornoi_largest_area(points: &[Point]) -> i32 {
-    let bbox = geometry::BoundingBox::from_points(points.iter().map(|&p| p.into()));
-    let mut grid = Grid::from_bbox(bbox);
-
-    for location in bbox.points() {
-        grid.check(location.into(), points);
-    }
-
-    grid.largest_area()
+    let seeds = seeds(points);
+    let bbox = geometry::BoundingBox::from_points(seeds.iter().cloned());
+
+    // Areas which reach the edge of the grid go on forever.
+    let voronoi = Voronoi::new(bbox, seeds, Metric::Manhattan);
+    voronoi
+        .largest_bounded()
+        .map(|(_, area)| area as i32)
+        .unwrap()
 }
 
 fn protected_area(points: &[Point], distance_limit: i32) -> usize {
-    let bbox = geometry::BoundingBox::from_points(points.iter().map(|&p| p.into()));
+    let seeds = seeds(points);
+    let bbox = geometry::BoundingBox::from_points(seeds.iter().cloned());
 
-    bbox.points()
-        .filter(|&location| {
-            let glocation: Point = location.into();
-            points.iter().map(|&p| glocation.distance(p)).sum::<i32>() < distance_limit
-        })
+    total_distance_region(bbox, &seeds, Metric::Manhattan, i64::from(distance_limit))
+        .values()
+        .filter(|&&inside| inside)
         .count()
 }
 
-fn closest(location: Point, points: &[Point]) -> Option<Point> {
-    let mut cl = points[0];
-    let mut unique = true;
-
-    for pt in points.iter().skip(1) {
-        if location.distance(*pt) < location.distance(cl) {
-            cl = *pt;
-            unique = true;
-        } else if location.distance(*pt) == location.distance(cl) {
-            unique = false;
-        }
-    }
-
-    if !unique {
-        None
-    } else {
-        Some(cl)
-    }
-}
-
 fn get_input() -> Result<Vec<Point>> {
     use crate::input;
 
@@ -182,9 +124,16 @@ mod test {
 
         assert_eq!(points[0], Point::new(1, 1));
 
-        assert_eq!(points[0].distance(Point::new(0, 3)), 3);
+        assert_eq!(
+            Metric::Manhattan.distance(points[0].into(), Point::new(0, 3).into()),
+            3
+        );
 
-        assert_eq!(closest(Point::new(0, 3), &points), Some(Point::new(1, 1)));
+        let seeds = seeds(&points);
+        let bbox = geometry::BoundingBox::from_points(seeds.iter().cloned()).margin(1);
+        let voronoi = Voronoi::new(bbox, seeds, Metric::Manhattan);
+        assert_eq!(voronoi.owner(Point::new(0, 3).into()), Some(0));
+        assert_eq!(voronoi.owner(Point::new(0, 4).into()), None);
 
         assert_eq!(vornoi_largest_area(&points), 17);
     }
@@ -12,6 +12,7 @@ use itertools::iproduct;
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use geometry::transform::{self, Transform};
 use geometry::{BoundingBox, Direction, Point, Position};
 
 type WSet = BTreeSet<Point>;
@@ -229,6 +230,46 @@ impl Ground {
 
         bbox
     }
+
+    fn points<'g>(&'g self) -> impl Iterator<Item = Point> + 'g {
+        self.grid
+            .iter()
+            .flat_map(|(y, xs)| xs.iter().map(move |x| Point::new(*x, *y)))
+    }
+
+    fn from_points(spring: Point, points: impl Iterator<Item = Point>) -> Self {
+        let mut ground = Self::new(spring);
+        for point in points {
+            ground.insert(point, Dirt::Clay);
+        }
+        ground
+    }
+
+    /// Rotate or reflect the clay and the spring about the origin.
+    pub fn transform(&self, transform: Transform) -> Self {
+        Self::from_points(
+            transform.apply(self.spring),
+            transform.apply_all(self.points()),
+        )
+    }
+
+    pub fn translate(&self, dx: Position, dy: Position) -> Self {
+        Self::from_points(
+            self.spring.translate(dx, dy),
+            transform::translate(self.points(), dx, dy),
+        )
+    }
+
+    /// Keep only the clay inside `bbox`, leaving the spring in place.
+    pub fn crop(&self, bbox: BoundingBox) -> Self {
+        Self::from_points(self.spring, transform::crop(self.points(), bbox))
+    }
+
+    /// Move the ground so the top left of the clay is at the origin.
+    pub fn normalize(&self) -> Self {
+        let origin = self.bbox().origin();
+        self.translate(-origin.x, -origin.y)
+    }
 }
 
 impl fmt::Display for Ground {
@@ -236,9 +277,8 @@ impl fmt::Display for Ground {
         let mut bbox = self.bbox().horizontal_margin(1);
         bbox.include(self.spring);
 
-        for y in bbox.vertical() {
-            for x in bbox.horizontal() {
-                let point = Point::new(x, y);
+        for row in bbox.rows() {
+            for point in row {
                 if point == self.spring {
                     write!(f, "+")?;
                 } else {
@@ -620,9 +660,8 @@ impl fmt::Display for WellSystem {
         let mut bbox = self.bbox().horizontal_margin(1);
         bbox.include(self.ground.spring);
 
-        for y in bbox.vertical() {
-            for x in bbox.horizontal() {
-                let point = Point::new(x, y);
+        for row in bbox.rows() {
+            for point in row {
                 if point == self.ground.spring {
                     write!(f, "+")?;
                 } else if let Some(flow) = self.flow(point) {
@@ -669,6 +708,29 @@ mod test {
         assert_eq!(example_ground().to_string(), ground_diagram);
     }
 
+    #[test]
+    fn transform_ground() {
+        let ground = example_ground();
+        let mirrored = ground
+            .to_string()
+            .lines()
+            .map(|l| l.chars().rev().collect::<String>() + "\n")
+            .collect::<String>();
+        assert_eq!(
+            ground.transform(Transform::FlipHorizontal).to_string(),
+            mirrored
+        );
+
+        let moved = ground.translate(-500, 3);
+        assert_eq!(moved.spring, Point::new(0, 3));
+        assert_eq!(moved.translate(500, -3).to_string(), ground.to_string());
+        assert_eq!(ground.normalize().bbox().origin(), Point::new(0, 0));
+
+        let cropped = ground.crop(BoundingBox::new(495, 497, 0, 13));
+        assert_eq!(cropped.bbox(), BoundingBox::new(495, 497, 2, 7));
+        assert_eq!(cropped.spring, ground.spring);
+    }
+
     fn example_ground() -> Ground {
         let scans = include_str!("../example.txt")
             .lines()